@@ -0,0 +1,243 @@
+use std::{fmt::Display, fs::{read_to_string, OpenOptions}, io::{ErrorKind, Write}, path::{Path, PathBuf}};
+
+use color_eyre::eyre::Context;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::pages::bot::BotSettings;
+
+/// The audit log botmon appends to next to the leo config
+pub const AUDIT_FILE: &str = "botmon_audit.jsonl";
+/// Checkpoints as they were before botmon changed them, kept next to the audit log
+pub const UNDO_FILE: &str = "botmon_undo.jsonl";
+
+/// A change botmon can make to a bot's cron entry
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BotAction {
+    Pause,
+    Resume,
+    /// Sets `trigger` to now so the bot is invoked on the next cron tick
+    Trigger,
+    /// Moves a read checkpoint to another event id
+    Checkpoint,
+}
+
+impl BotAction {
+    /// Pauses a running bot or resumes a paused one
+    pub fn toggle_pause(bot: &BotSettings) -> Self {
+        if bot.paused.unwrap_or_default() {
+            BotAction::Resume
+        } else {
+            BotAction::Pause
+        }
+    }
+
+    /// The past tense, for reporting what was done
+    pub fn done(&self) -> &'static str {
+        match self {
+            BotAction::Pause => "paused",
+            BotAction::Resume => "resumed",
+            BotAction::Trigger => "triggered",
+            BotAction::Checkpoint => "moved the checkpoint of",
+        }
+    }
+}
+
+impl Display for BotAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BotAction::Pause => write!(f, "pause"),
+            BotAction::Resume => write!(f, "resume"),
+            BotAction::Trigger => write!(f, "trigger"),
+            BotAction::Checkpoint => write!(f, "move the checkpoint of"),
+        }
+    }
+}
+
+/// A read checkpoint being moved, by the queue's key in `checkpoints.read`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckpointChange {
+    pub queue: String,
+    pub before: Option<String>,
+    pub after: String,
+}
+
+/// An action waiting on the user to confirm it, along with the bot as it was loaded
+#[derive(Debug, Clone)]
+pub struct PendingAction {
+    pub action: BotAction,
+    pub bot: BotSettings,
+    /// Only set for [`BotAction::Checkpoint`]
+    pub checkpoint: Option<CheckpointChange>,
+}
+
+/// One line of the audit log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub time: i64,
+    pub user: String,
+    pub bus: String,
+    pub bot: String,
+    pub action: BotAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checkpoint: Option<CheckpointChange>,
+    /// Why the write failed, missing when it succeeded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    pub fn new(bus: &str, bot: &str, action: BotAction, error: Option<String>, time: i64) -> Self {
+        Self {
+            time,
+            user: current_user(),
+            bus: bus.to_owned(),
+            bot: bot.to_owned(),
+            action,
+            checkpoint: None,
+            error,
+        }
+    }
+}
+
+/// A checkpoint as it was just before botmon moved it. It's written as pending before the change is sent
+/// and again once the write succeeds, so a write that failed is never offered as an undo
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UndoRecord {
+    pub time: i64,
+    pub user: String,
+    pub bus: String,
+    pub bot: String,
+    #[serde(flatten)]
+    pub change: CheckpointChange,
+    /// The change hasn't been confirmed as written yet
+    #[serde(default)]
+    pub pending: bool,
+}
+
+impl UndoRecord {
+    pub fn new(bus: &str, bot: &str, change: CheckpointChange, time: i64) -> Self {
+        Self {
+            time,
+            user: current_user(),
+            bus: bus.to_owned(),
+            bot: bot.to_owned(),
+            change,
+            pending: false,
+        }
+    }
+}
+
+fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Where every write to a bus is recorded, one json object per line, along with the undo records for checkpoints
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    pub path: PathBuf,
+    pub undo_path: PathBuf,
+}
+
+impl AuditLog {
+    /// Uses `path`, or `botmon_audit.jsonl` next to the leo config. Undo records go in `botmon_undo.jsonl` beside it
+    pub fn new(path: Option<&str>, config_path: Option<&str>) -> Self {
+        let path = match path {
+            Some(path) => PathBuf::from(path),
+            None => Path::new(config_path.unwrap_or("./config.json")).with_file_name(AUDIT_FILE),
+        };
+        Self {
+            undo_path: path.with_file_name(UNDO_FILE),
+            path,
+        }
+    }
+
+    pub fn append(&self, entry: &AuditEntry) -> color_eyre::Result<()> {
+        append_line(&self.path, entry)
+    }
+
+    pub fn record_undo(&self, record: &UndoRecord) -> color_eyre::Result<()> {
+        append_line(&self.undo_path, record)
+    }
+
+    /// The newest undo record for a bot's read of `queue` on `bus`
+    pub fn last_undo(&self, bus: &str, bot: &str, queue: &str) -> color_eyre::Result<Option<UndoRecord>> {
+        let records: Vec<UndoRecord> = read_lines(&self.undo_path)?;
+        Ok(records.into_iter().rev().find(|a| !a.pending && a.bus == bus && a.bot == bot && a.change.queue == queue))
+    }
+}
+
+fn append_line<T: Serialize>(path: &Path, value: &T) -> color_eyre::Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .wrap_err_with(|| format!("failed to open {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(value)?)
+        .wrap_err_with(|| format!("failed to write to {}", path.display()))?;
+    Ok(())
+}
+
+/// Every line of a json lines file, nothing when it hasn't been written yet
+fn read_lines<T: DeserializeOwned>(path: &Path) -> color_eyre::Result<Vec<T>> {
+    let raw = match read_to_string(path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e).wrap_err_with(|| format!("failed to read {}", path.display())),
+    };
+    raw.lines()
+        .filter(|a| !a.trim().is_empty())
+        .enumerate()
+        .map(|(index, line)| serde_json::from_str(line).wrap_err_with(|| format!("{} line {} is not valid json", path.display(), index + 1)))
+        .collect()
+}
+
+#[cfg(test)]
+mod actions_tests {
+    use std::fs::{create_dir_all, read_to_string, remove_dir_all, remove_file};
+
+    use super::{AuditEntry, AuditLog, BotAction, CheckpointChange, UndoRecord};
+
+    #[test]
+    fn audit_entries_are_appended_as_json_lines() {
+        let path = std::env::temp_dir().join(format!("botmon_audit_{}.jsonl", std::process::id()));
+        let _ = remove_file(&path);
+        let log = AuditLog::new(path.to_str(), None);
+
+        log.append(&AuditEntry::new("local", "bot:order_loader", BotAction::Pause, None, 1721044800000)).unwrap();
+        log.append(&AuditEntry::new("local", "bot:order_loader", BotAction::Trigger, Some("changed since it was loaded".to_string()), 1721044860000)).unwrap();
+
+        let entries: Vec<AuditEntry> = read_to_string(&path).unwrap()
+            .lines()
+            .map(|a| serde_json::from_str(a).unwrap())
+            .collect();
+        remove_file(&path).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].action, BotAction::Pause);
+        assert_eq!(entries[0].error, None);
+        assert_eq!(entries[1].action, BotAction::Trigger);
+        assert_eq!(entries[1].error.as_deref(), Some("changed since it was loaded"));
+    }
+
+    #[test]
+    fn failed_checkpoint_moves_are_not_undone() {
+        let dir = std::env::temp_dir().join(format!("botmon_undo_{}", std::process::id()));
+        create_dir_all(&dir).unwrap();
+        let log = AuditLog::new(dir.join("audit.jsonl").to_str(), None);
+        let change = |before: &str, after: &str| CheckpointChange { queue: "queue:orders".to_string(), before: Some(before.to_string()), after: after.to_string() };
+
+        // A to B goes through, B to C fails and is only ever recorded as pending
+        let a_to_b = UndoRecord::new("local", "bot:a", change("A", "B"), 1);
+        log.record_undo(&UndoRecord { pending: true, ..a_to_b.clone() }).unwrap();
+        log.record_undo(&a_to_b).unwrap();
+        log.record_undo(&UndoRecord { pending: true, ..UndoRecord::new("local", "bot:a", change("B", "C"), 2) }).unwrap();
+
+        let undo = log.last_undo("local", "bot:a", "queue:orders").unwrap();
+        remove_dir_all(&dir).unwrap();
+
+        assert_eq!(undo.and_then(|a| a.change.before).as_deref(), Some("A"));
+    }
+}
@@ -0,0 +1,407 @@
+use std::{collections::HashMap, fmt::Display, fs::read_to_string, path::{Path, PathBuf}};
+
+use chrono::Duration;
+use color_eyre::eyre::{bail, Context};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::{bot_stats::BotDynamoStatsRecord, health::{Health, HealthStatus}, lag::read_lags, loader::LoadMessage, pages::{bot::BotSettings, queue::{queue_name, QueueRecord}}};
+
+/// The rules file botmon looks for next to the leo config
+pub const ALERTS_FILE: &str = "alerts.json";
+
+/// What a rule watches for
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "when", rename_all = "snake_case")]
+pub enum Condition {
+    /// Execution errors in the stats over the last `window_minutes`
+    Errors { above: u32, window_minutes: i64 },
+    /// Errors in a row recorded on the cron table
+    ErrorCount { above: u32 },
+    /// A read more than `above_minutes` behind the head of its queue
+    ReadLag { above_minutes: i64 },
+    Paused,
+    /// The bot's health is `at_least` this bad
+    Health { at_least: HealthStatus },
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Condition::Errors { above, window_minutes } => write!(f, "errors > {above} in {window_minutes}m"),
+            Condition::ErrorCount { above } => write!(f, "error count > {above}"),
+            Condition::ReadLag { above_minutes } => write!(f, "read lag > {above_minutes}m"),
+            Condition::Paused => write!(f, "paused"),
+            Condition::Health { at_least } => write!(f, "health {}", at_least.to_string().to_lowercase()),
+        }
+    }
+}
+
+/// A condition and the bots or queue it applies to
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AlertRule {
+    /// Shown in the alerts panel and notifications, the condition is used when missing
+    pub name: Option<String>,
+    /// The bot to watch, every bot when missing
+    pub bot: Option<String>,
+    /// Limits `read_lag` to a single queue
+    pub queue: Option<String>,
+    #[serde(flatten)]
+    pub condition: Condition,
+}
+
+impl AlertRule {
+    pub fn name(&self) -> String {
+        match (self.name.as_ref(), self.bot.as_ref(), self.queue.as_ref()) {
+            (Some(name), _, _) => name.clone(),
+            (None, Some(bot), _) => format!("{bot} {}", self.condition),
+            (None, None, Some(queue)) => format!("{queue} {}", self.condition),
+            (None, None, None) => self.condition.to_string(),
+        }
+    }
+
+    fn matches_bot(&self, bot_id: &str) -> bool {
+        self.bot.as_deref().is_none_or(|a| a == bot_id || a == bot_id.replace("bot:", ""))
+    }
+}
+
+/// The contents of the rules file
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AlertConfig {
+    #[serde(default)]
+    pub rules: Vec<AlertRule>,
+    /// Firing and resolved alerts are POSTed here as json
+    pub webhook: Option<String>,
+    /// Also show a desktop notification for each alert
+    #[serde(default)]
+    pub desktop: bool,
+}
+
+impl AlertConfig {
+    /// Reads the rules from `path`, or from `alerts.json` next to the leo config. Only a path that was asked for has to exist
+    pub fn load(path: Option<&str>, config_path: Option<&str>) -> color_eyre::Result<Self> {
+        let rules_path = match path {
+            Some(path) => PathBuf::from(path),
+            None => Path::new(config_path.unwrap_or("./config.json")).with_file_name(ALERTS_FILE),
+        };
+        if path.is_none() && !rules_path.exists() {
+            return Ok(Self::default());
+        }
+
+        let raw = read_to_string(&rules_path).wrap_err_with(|| format!("failed to read alert rules {}", rules_path.display()))?;
+        serde_json::from_str(&raw).wrap_err_with(|| format!("{} is not a valid alert rules file", rules_path.display()))
+    }
+}
+
+/// A rule that is firing for a bot, or a bot and queue
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Alert {
+    /// Identifies the alert across refreshes
+    pub key: String,
+    pub rule: String,
+    pub bot: String,
+    pub queue: Option<String>,
+    pub message: String,
+}
+
+impl Alert {
+    fn new(rule: &AlertRule, bot: &str, queue: Option<String>, message: String) -> Self {
+        let name = rule.name();
+        Self {
+            key: format!("{name}|{bot}|{}", queue.as_deref().unwrap_or_default()),
+            rule: name,
+            bot: bot.replace("bot:", ""),
+            queue,
+            message,
+        }
+    }
+}
+
+/// Checks every rule against the loaded bots and stats, returning the alerts that are firing
+pub fn evaluate(rules: &[AlertRule], bots: &[BotSettings], stats: &[BotDynamoStatsRecord], records: &[QueueRecord], health: &HashMap<String, Health>, now: i64) -> Vec<Alert> {
+    let lags = read_lags(bots, stats, records, now);
+    let mut alerts = vec![];
+
+    for rule in rules {
+        for bot in bots.iter().filter(|a| rule.matches_bot(&a.id)) {
+            match &rule.condition {
+                Condition::Errors { above, window_minutes } => {
+                    let since = now - Duration::minutes(*window_minutes).num_milliseconds();
+                    let errors: u64 = stats.iter()
+                        .filter(|a| a.id == bot.id && a.time >= since)
+                        .filter_map(|a| a.current.execution.as_ref()?.errors)
+                        .map(u64::from)
+                        .sum();
+                    if errors > u64::from(*above) {
+                        alerts.push(Alert::new(rule, &bot.id, None, format!("{errors} errors in the last {window_minutes}m")));
+                    }
+                },
+                Condition::ErrorCount { above } => {
+                    let error_count = bot.error_count.unwrap_or_default();
+                    if error_count > *above {
+                        alerts.push(Alert::new(rule, &bot.id, None, format!("{error_count} errors in a row")));
+                    }
+                },
+                Condition::ReadLag { above_minutes } => {
+                    let limit = Duration::minutes(*above_minutes).num_milliseconds();
+                    let reads = lags.iter()
+                        .filter(|((id, queue), _)| *id == bot.id && rule.queue.as_ref().is_none_or(|a| queue_name(a) == *queue))
+                        .filter(|(_, lag)| lag.behind() > limit);
+                    for ((_, queue), lag) in reads {
+                        let minutes = lag.behind() / Duration::minutes(1).num_milliseconds();
+                        alerts.push(Alert::new(rule, &bot.id, Some(queue.clone()), format!("{minutes}m behind {queue}")));
+                    }
+                },
+                Condition::Paused => {
+                    if bot.paused.unwrap_or_default() {
+                        alerts.push(Alert::new(rule, &bot.id, None, "paused".to_string()));
+                    }
+                },
+                Condition::Health { at_least } => {
+                    if let Some(health) = health.get(&bot.id.replace("bot:", "")).filter(|a| a.status >= *at_least) {
+                        alerts.push(Alert::new(rule, &bot.id, None, format!("{}: {}", health.status, health.reason)));
+                    }
+                },
+            }
+        }
+    }
+
+    alerts.sort_by(|a, b| a.key.cmp(&b.key));
+    alerts
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertStatus {
+    Firing,
+    Resolved,
+}
+
+/// An alert starting or stopping
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AlertEvent {
+    pub status: AlertStatus,
+    /// Epoch millis of the refresh that noticed the change
+    pub time: i64,
+    #[serde(flatten)]
+    pub alert: Alert,
+}
+
+/// The rules being checked, what is firing and what has changed
+#[derive(Debug, Default)]
+pub struct AlertState {
+    pub config: AlertConfig,
+    /// Alerts firing as of the last refresh and when each started
+    pub firing: Vec<(Alert, i64)>,
+    /// Every alert that started or stopped, newest first
+    pub history: Vec<AlertEvent>,
+    /// The last notification that couldn't be sent
+    pub notify_error: Option<String>,
+    pub vertical_scroll: usize,
+    client: reqwest::Client,
+}
+
+impl AlertState {
+    const HISTORY_LIMIT: usize = 500;
+
+    pub fn new(config: AlertConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    /// Swaps in the alerts firing now, returning the ones that started or stopped since the last refresh
+    pub fn update(&mut self, alerts: Vec<Alert>, now: i64) -> Vec<AlertEvent> {
+        let mut events = vec![];
+        let mut started: HashMap<String, i64> = HashMap::new();
+
+        for (alert, since) in self.firing.drain(..) {
+            match alerts.iter().any(|a| a.key == alert.key) {
+                true => { started.insert(alert.key, since); },
+                false => events.push(AlertEvent { status: AlertStatus::Resolved, time: now, alert }),
+            }
+        }
+        for alert in alerts {
+            let since = match started.get(&alert.key) {
+                Some(since) => *since,
+                None => {
+                    events.push(AlertEvent { status: AlertStatus::Firing, time: now, alert: alert.clone() });
+                    now
+                },
+            };
+            self.firing.push((alert, since));
+        }
+
+        for event in events.iter() {
+            self.history.insert(0, event.clone());
+        }
+        self.history.truncate(Self::HISTORY_LIMIT);
+
+        events
+    }
+
+    /// Sends the events to the webhook and desktop on a tokio task, any failure is reported over `tx`
+    pub fn notify(&self, bus: &str, events: Vec<AlertEvent>, tx: UnboundedSender<LoadMessage>) {
+        if events.is_empty() || (self.config.webhook.is_none() && !self.config.desktop) {
+            return;
+        }
+        let (client, config, bus) = (self.client.clone(), self.config.clone(), bus.to_owned());
+
+        tokio::spawn(async move {
+            for event in events {
+                if let Some(url) = config.webhook.as_ref() {
+                    if let Err(e) = post_webhook(&client, url, &bus, &event).await {
+                        let _ = tx.send(LoadMessage::NotifyFailed(format!("{e:#}")));
+                    }
+                }
+                if config.desktop {
+                    if let Err(e) = desktop_notification(&event).await {
+                        let _ = tx.send(LoadMessage::NotifyFailed(format!("{e:#}")));
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    bus: &'a str,
+    #[serde(flatten)]
+    event: &'a AlertEvent,
+}
+
+/// POSTs an event as json
+pub async fn post_webhook(client: &reqwest::Client, url: &str, bus: &str, event: &AlertEvent) -> color_eyre::Result<()> {
+    let response = client.post(url)
+        .json(&WebhookPayload { bus, event })
+        .send()
+        .await
+        .wrap_err_with(|| format!("failed to reach webhook {url}"))?;
+    if !response.status().is_success() {
+        bail!("webhook {url} responded {}", response.status());
+    }
+    Ok(())
+}
+
+/// Quotes `value` as an AppleScript string literal, where only backslashes and double quotes are escaped
+fn applescript_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Shows an event with the platform's notifier, `notify-send` on linux and `osascript` on macos
+async fn desktop_notification(event: &AlertEvent) -> color_eyre::Result<()> {
+    let title = match event.status {
+        AlertStatus::Firing => format!("botmon: {}", event.alert.rule),
+        AlertStatus::Resolved => format!("botmon: resolved {}", event.alert.rule),
+    };
+    let body = format!("{} {}", event.alert.bot, event.alert.message);
+
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = tokio::process::Command::new("osascript");
+        command.arg("-e").arg(format!("display notification {} with title {}", applescript_string(&body), applescript_string(&title)));
+        command
+    } else {
+        let mut command = tokio::process::Command::new("notify-send");
+        command.arg(title).arg(body);
+        command
+    };
+    let status = command.status().await.wrap_err("failed to run the desktop notifier")?;
+    if !status.success() {
+        bail!("desktop notifier exited with {status}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod alerts_tests {
+    use std::collections::HashMap;
+
+    use tokio::{io::{AsyncReadExt, AsyncWriteExt}, net::TcpListener};
+
+    use crate::{pages::bot::BotSettings, source::fixtures::{self, NOW}};
+
+    use super::{applescript_string, evaluate, post_webhook, AlertConfig, AlertState, AlertStatus};
+
+    #[test]
+    fn alerts_fire_and_resolve_across_refreshes() {
+        let (bots, stats) = (fixtures::bots(), fixtures::minute_stats());
+        let config: AlertConfig = serde_json::from_str(r#"{"rules": [
+            {"when": "paused"},
+            {"name": "legacy errors", "bot": "legacy_customer_sync", "when": "errors", "above": 1, "window_minutes": 60},
+            {"queue": "customers", "when": "read_lag", "above_minutes": 30}
+        ]}"#).unwrap();
+
+        let alerts = evaluate(&config.rules, &bots, &stats, &[], &HashMap::new(), NOW);
+        let fired: Vec<(&str, &str)> = alerts.iter().map(|a| (a.rule.as_str(), a.bot.as_str())).collect();
+        assert_eq!(fired, vec![
+            ("customers read lag > 30m", "legacy_customer_sync"),
+            ("legacy errors", "legacy_customer_sync"),
+            ("paused", "customer_loader"),
+        ]);
+
+        let mut state = AlertState::new(config);
+        assert_eq!(state.update(alerts.clone(), NOW).len(), 3);
+        assert!(state.update(alerts.clone(), NOW + 1).is_empty());
+
+        let events = state.update(alerts[1..].to_vec(), NOW + 2);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].status, AlertStatus::Resolved);
+        assert_eq!(state.firing.len(), 2);
+        assert_eq!(state.firing[0].1, NOW);
+        assert_eq!(state.history.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn webhook_receives_the_event_as_json() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = vec![];
+            // The headers and body can arrive in separate writes, so read until the whole content-length is in
+            loop {
+                let mut chunk = [0; 1024];
+                let read = socket.read(&mut chunk).await.unwrap();
+                assert!(read > 0, "connection closed before the body arrived");
+                request.extend_from_slice(&chunk[..read]);
+
+                let text = String::from_utf8_lossy(&request).to_string();
+                let Some((headers, body)) = text.split_once("\r\n\r\n") else {
+                    continue;
+                };
+                let length: usize = headers.lines()
+                    .find_map(|a| Some(a.to_lowercase().strip_prefix("content-length:")?.trim().parse().unwrap()))
+                    .unwrap_or_default();
+                if body.len() >= length {
+                    break;
+                }
+            }
+            socket.write_all(b"HTTP/1.1 204 No Content\r\ncontent-length: 0\r\n\r\n").await.unwrap();
+            String::from_utf8_lossy(&request).to_string()
+        });
+
+        let config: AlertConfig = serde_json::from_str(r#"{"rules": [{"when": "paused"}]}"#).unwrap();
+        let bots: Vec<BotSettings> = serde_json::from_str(r#"[{"id": "bot:a", "paused": true}]"#).unwrap();
+        let mut state = AlertState::new(config);
+        let events = state.update(evaluate(&state.config.rules.clone(), &bots, &[], &[], &HashMap::new(), 0), 0);
+
+        post_webhook(&reqwest::Client::new(), &url, "test", &events[0]).await.unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /hook"));
+        let body: serde_json::Value = serde_json::from_str(request.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(body["bus"], "test");
+        assert_eq!(body["status"], "firing");
+        assert_eq!(body["bot"], "a");
+        assert_eq!(body["rule"], "paused");
+    }
+
+    #[test]
+    fn applescript_strings_escape_quotes_and_backslashes() {
+        assert_eq!(applescript_string(r#"bot "a" in C:\bus"#), r#""bot \"a\" in C:\\bus""#);
+        // Debug formatting would have turned this into \u{e9}, which AppleScript prints as is
+        assert_eq!(applescript_string("café"), "\"café\"");
+    }
+}
@@ -1,36 +1,88 @@
-use std::env::current_dir;
-use std::{collections::HashMap, time::Instant};
-use std::fs::read_to_string;
+use std::{collections::HashMap, sync::Arc, time::Instant};
 
-use aws_config::SdkConfig;
-use aws_sdk_dynamodb::Client;
 use chrono::{Duration, Utc};
-use color_eyre::eyre::{bail, Context, Error};
+use color_eyre::eyre::{bail, Context};
 use crossterm::event::{self, poll, Event, KeyCode, KeyEvent, KeyEventKind};
-use futures::FutureExt;
 use ratatui::Frame;
 use throbber_widgets_tui::ThrobberState;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
 use tui_input::backend::crossterm::EventHandler;
 
-use crate::dynamo::{get_all_bot_details, get_all_bot_stats_for_period, AllBucketsBuilder, Period};
+use crate::actions::{AuditEntry, AuditLog, BotAction, PendingAction, UndoRecord};
+use crate::alerts::{evaluate, AlertConfig, AlertState};
+use crate::loader::{spawn_bot_action, spawn_bot_history, spawn_entries, spawn_load, spawn_range_stats, spawn_stream_events, LoadMessage, LoadProgress};
 use crate::pages::bus_select::BusSelectState;
-use crate::{leo_config::LeoConfig, pages::bot::BotPageState, ui::render_ui, Tui, AppParams};
+use crate::pages::checkpoint::CheckpointEditor;
+use crate::pages::entries::{EntryBrowserState, EntryTable};
+use crate::pages::events::EventBrowserState;
+use crate::pages::logs::LogPaneState;
+use crate::pages::overview::OverviewPageState;
+use crate::lag::{furthest_behind, ReadLag};
+use crate::pages::queue::QueuePageState;
+use crate::pages::topology::TopologyState;
+use crate::topology::{Node, Topology};
+use crate::source::{BotmonSource, DynamoSource, FileSource};
+use crate::stream::StreamPosition;
+use crate::{leo_config::{load_buses, override_endpoint, LeoConfig}, pages::bot::{BotPageState, BotSettings}, ui::render_ui, Tui, AppParams};
 
 #[derive(Debug)]
 pub struct AppState {
     pub mode: AppTab,
     pub bus_select: BusSelectState,
     pub tab_index: usize,
-    pub chart_data: Vec<(f64, f64)>,
     pub bot_page: BotPageState,
+    pub queue_page: QueuePageState,
+    pub overview_page: OverviewPageState,
+    pub alerts: AlertState,
+    /// The graph around a bot or queue, built when the topology view is opened
+    pub topology: Option<TopologyState>,
+    /// Where Tab goes back to from the topology view
+    topology_parent: AppTab,
+    /// Where Tab goes back to from the bot view
+    bot_view_parent: AppTab,
+    /// The events on a queue, opened from the queue or bot view
+    pub events: Option<EventBrowserState>,
+    /// Where Tab goes back to from the event browser
+    events_parent: AppTab,
+    /// The system or settings table, opened from the main menu or a bot's systems
+    pub entries: Option<EntryBrowserState>,
+    /// Where Tab goes back to from the entry browser
+    entries_parent: AppTab,
+    /// Set by `--allow-writes`, bots can't be paused, resumed or triggered without it
+    pub allow_writes: bool,
+    /// Set by `--allow-prod-checkpoints`, checkpoints on production buses can't be moved without it
+    allow_prod_checkpoints: bool,
+    pub audit_log: AuditLog,
+    /// The read checkpoint being moved for the viewed bot
+    pub checkpoint: Option<CheckpointEditor>,
+    /// A write shown in the confirmation dialog, nothing is sent until it's confirmed
+    pub pending_action: Option<PendingAction>,
+    /// What happened to the last write, or why it wasn't allowed
+    pub action_status: Option<Result<String, String>>,
     pub start_time: Instant,
     pub refresh_at: Instant,
     pub refresh_rate: Duration,
+    pub last_refresh: Option<Instant>,
+    pub refresh_error: Option<String>,
+    /// Something the last load couldn't apply that didn't stop it, like the viewed bot being removed
+    pub refresh_warning: Option<String>,
+    pub load_progress: LoadProgress,
+    /// Pages, items and capacity used by the last completed load
+    pub last_load: Option<LoadProgress>,
+    refresh_task: Option<JoinHandle<()>>,
+    history_task: Option<JoinHandle<()>>,
+    range_task: Option<JoinHandle<()>>,
+    action_task: Option<JoinHandle<()>>,
+    events_task: Option<JoinHandle<()>>,
+    entries_task: Option<JoinHandle<()>>,
+    load_tx: UnboundedSender<LoadMessage>,
+    load_rx: UnboundedReceiver<LoadMessage>,
     pub selected_bus: Option<String>,
     pub buses: HashMap<String, LeoConfig>,
     pub loaded_config: Option<LeoConfig>,
-    pub aws_config: SdkConfig,
-    pub client: Client,
+    /// Where the selected bus is read from, `None` until a bus is picked
+    pub source: Option<Arc<dyn BotmonSource>>,
     pub throbber_state: ThrobberState,
     pub tick_rate: Duration,
     exit: bool
@@ -51,9 +103,14 @@ impl AppState {
                 self.handle_events().await.wrap_err("handle events failed")?;
 
             }
+            
+            self.check_refresh()?;
 
-            if last_tick.elapsed() >= self.tick_rate.to_std().unwrap() && self.mode == AppTab::Loading {
-                self.on_tick();
+            // Keep ticking outside of loading so the refresh countdown is redrawn without spinning
+            if last_tick.elapsed() >= self.tick_rate.to_std().unwrap() {
+                if self.mode == AppTab::Loading {
+                    self.on_tick();
+                }
                 last_tick = Instant::now()
             }
             
@@ -61,6 +118,352 @@ impl AppState {
         Ok(())
     }
     
+    /// Applies any finished background loads and kicks off a new one once `refresh_at` has passed
+    fn check_refresh(&mut self) -> color_eyre::Result<()> {
+        while let Ok(message) = self.load_rx.try_recv() {
+            match message {
+                LoadMessage::Progress { table, summary } => self.load_progress.update(table, summary),
+                LoadMessage::Loaded { stats, bots, queues, progress } => {
+                    self.refresh_task = None;
+                    self.refresh_warning = [progress.error.clone(), self.bot_page.apply_refresh(stats, bots, queues)]
+                        .into_iter()
+                        .flatten()
+                        .reduce(|a, b| format!("{a}, {b}"));
+                    self.last_load = Some(progress);
+                    self.queue_page.apply_refresh(&self.bot_page.stats, self.bot_page.all_bots.as_deref().unwrap_or_default(), &self.bot_page.queue_records);
+                    self.overview_page.apply_refresh(self.bot_page.all_bots.as_deref().unwrap_or_default(), &self.bot_page.stats, &self.bot_page.queue_records, &self.bot_page.health);
+                    self.check_alerts();
+                    if let Some(topology) = self.topology.as_mut() {
+                        topology.refresh(Topology::build(self.bot_page.all_bots.as_deref().unwrap_or_default(), &self.bot_page.stats));
+                    }
+                    self.last_refresh = Some(Instant::now());
+                    self.refresh_at = Instant::now() + self.refresh_rate.to_std()?;
+                    self.refresh_error = None;
+                    if self.mode == AppTab::Loading {
+                        self.mode = AppTab::Main;
+                    }
+                    // Keep the viewed bot's history as current as the rest of the page
+                    if matches!(self.mode, AppTab::BotView | AppTab::BotLog) && self.history_task.is_none() {
+                        self.fetch_bot_history();
+                    }
+                    if self.mode == AppTab::QueueView && self.queue_page.needs_range_stats() && self.range_task.is_none() {
+                        self.fetch_range_stats();
+                    }
+                },
+                LoadMessage::Failed(e) => {
+                    self.refresh_task = None;
+                    if self.mode == AppTab::Loading {
+                        self.load_progress.error = Some(e);
+                    } else {
+                        self.refresh_error = Some(e);
+                    }
+                },
+                LoadMessage::BotHistory { bot_id, range, stats } => {
+                    self.history_task = None;
+                    // The view may have moved on to another bot or range while this was loading
+                    if let Some(view) = self.bot_page.selected_bot.as_mut().filter(|a| a.setting.id == bot_id && a.range == range) {
+                        match stats {
+                            Ok(stats) => view.apply_history(stats),
+                            Err(e) => view.history_error = Some(e),
+                        }
+                    }
+                },
+                LoadMessage::NotifyFailed(e) => self.alerts.notify_error = Some(e),
+                LoadMessage::ActionDone { bot_id, action, checkpoint, result } => {
+                    self.action_task = None;
+                    let succeeded = result.is_ok();
+                    let bus = self.selected_bus.as_deref().unwrap_or_default();
+                    // Only a checkpoint that was actually moved can be undone
+                    let undone = match (succeeded, checkpoint.clone()) {
+                        (true, Some(change)) => self.audit_log.record_undo(&UndoRecord::new(bus, &bot_id, change, Utc::now().timestamp_millis())),
+                        _ => Ok(()),
+                    };
+                    let entry = AuditEntry {
+                        checkpoint,
+                        ..AuditEntry::new(bus, &bot_id, action, result.clone().err(), Utc::now().timestamp_millis())
+                    };
+                    let logged = self.audit_log.append(&entry).and(undone);
+                    self.action_status = Some(match (result, logged) {
+                        (Ok(()), Ok(())) => Ok(format!("{} {bot_id}", action.done())),
+                        (Ok(()), Err(e)) => Err(format!("{} {bot_id} but {e:#}", action.done())),
+                        (Err(e), _) => Err(format!("{action} {bot_id} failed: {e}")),
+                    });
+                    // Pick up the change rather than waiting for the next refresh
+                    if succeeded && self.refresh_task.is_none() {
+                        self.start_load();
+                    }
+                },
+                LoadMessage::StreamEvents { queue, position, events } => {
+                    self.events_task = None;
+                    if let Some(browser) = self.events.as_mut().filter(|a| a.queue == queue) {
+                        browser.apply(position, events);
+                    }
+                },
+                LoadMessage::Entries { table, entries } => {
+                    self.entries_task = None;
+                    if let Some(browser) = self.entries.as_mut().filter(|a| a.table == table) {
+                        browser.apply(entries);
+                    }
+                },
+                LoadMessage::RangeStats { range, stats } => {
+                    self.range_task = None;
+                    match stats {
+                        Ok(stats) => self.queue_page.apply_range_stats(range, stats),
+                        Err(e) => self.refresh_error = Some(e),
+                    }
+                },
+            }
+        }
+        
+        // Only refresh once something has been loaded for the selected bus
+        if self.last_refresh.is_none() || self.refresh_task.is_some() || Instant::now() < self.refresh_at {
+            return Ok(());
+        }
+        
+        self.start_load();
+        self.refresh_at = Instant::now() + self.refresh_rate.to_std()?;
+        
+        Ok(())
+    }
+    
+    /// Checks the alert rules against the latest load and sends out any alerts that started or stopped
+    fn check_alerts(&mut self) {
+        let alerts = evaluate(
+            &self.alerts.config.rules,
+            self.bot_page.all_bots.as_deref().unwrap_or_default(),
+            &self.bot_page.stats,
+            &self.bot_page.queue_records,
+            &self.bot_page.health,
+            Utc::now().timestamp_millis(),
+        );
+        let events = self.alerts.update(alerts, Utc::now().timestamp_millis());
+        self.alerts.notify(self.selected_bus.as_deref().unwrap_or_default(), events, self.load_tx.clone());
+    }
+    
+    /// Opens the topology view centered on `focus`
+    fn open_topology(&mut self, focus: Node) {
+        let topology = Topology::build(self.bot_page.all_bots.as_deref().unwrap_or_default(), &self.bot_page.stats);
+        self.topology = Some(TopologyState::new(topology, focus));
+        self.topology_parent = self.mode;
+        self.mode = AppTab::Topology;
+    }
+    
+    /// Opens the event browser on `queue` and fetches the page at `position`
+    fn open_events(&mut self, queue: &str, position: StreamPosition, checkpoint: Option<(String, String)>) {
+        self.events = Some(EventBrowserState::new(queue, position.clone(), checkpoint));
+        self.events_parent = self.mode;
+        self.mode = AppTab::Events;
+        self.fetch_events(position);
+    }
+    
+    /// Opens the event browser at the bot's read checkpoint on the queue it's furthest behind the head of, where it's most likely stuck
+    fn open_bot_events(&mut self, bot_id: &str, lags: &HashMap<String, ReadLag>) {
+        let furthest = furthest_behind(lags)
+            .and_then(|(queue, lag)| Some((queue.to_owned(), lag.checkpoint.clone()?)));
+        match furthest {
+            Some((queue, eid)) => self.open_events(&queue, StreamPosition::At(eid.clone()), Some((bot_id.to_owned(), eid))),
+            None => self.action_status = Some(Err(format!("{bot_id} has no read checkpoints to browse events from"))),
+        }
+    }
+    
+    /// Spawns a fetch of a page of the browsed queue, the results are picked up in `check_refresh`
+    fn fetch_events(&mut self, position: StreamPosition) {
+        if let (Some(source), Some(browser)) = (self.source.as_ref(), self.events.as_ref()) {
+            if let Some(task) = self.events_task.take() {
+                task.abort();
+            }
+            self.events_task = Some(spawn_stream_events(source.clone(), browser.queue.clone(), position, self.load_tx.clone()));
+        }
+    }
+    
+    /// Handles keys in the event browser, fetching the page it moves to
+    fn events_key(&mut self, key_event: KeyEvent) {
+        let Some(browser) = self.events.as_mut() else {
+            return;
+        };
+        if key_event.code == KeyCode::Tab && browser.jump.is_none() {
+            self.events = None;
+            if let Some(task) = self.events_task.take() {
+                task.abort();
+            }
+            self.mode = self.events_parent;
+            return;
+        }
+        if let Some(position) = browser.handle_key(key_event, self.bot_page.all_bots.as_deref().unwrap_or_default()) {
+            self.fetch_events(position);
+        }
+    }
+    
+    /// Opens the browser on the system or settings table, selecting `focus` once it loads
+    fn open_entries(&mut self, table: EntryTable, focus: Option<String>) {
+        self.entries = Some(EntryBrowserState::new(table, self.bot_page.all_bots.as_deref().unwrap_or_default(), focus));
+        self.entries_parent = self.mode;
+        self.mode = AppTab::Entries;
+        if let Some(source) = self.source.as_ref() {
+            if let Some(task) = self.entries_task.take() {
+                task.abort();
+            }
+            self.entries_task = Some(spawn_entries(source.clone(), table, self.load_tx.clone()));
+        }
+    }
+    
+    /// Handles keys in the entry browser, opening the bot view when a linked bot is picked
+    fn entries_key(&mut self, key_event: KeyEvent) -> color_eyre::Result<()> {
+        let Some(browser) = self.entries.as_mut() else {
+            return Ok(());
+        };
+        if key_event.code == KeyCode::Tab {
+            self.entries = None;
+            if let Some(task) = self.entries_task.take() {
+                task.abort();
+            }
+            self.mode = self.entries_parent;
+            return Ok(());
+        }
+        if let Some(bot) = browser.handle_key(key_event) {
+            self.bot_page.selected_bot_name = Some(bot);
+            self.bot_page.get_bot_details()?;
+            self.fetch_bot_history();
+            self.bot_view_parent = AppTab::Entries;
+            self.mode = AppTab::BotView;
+        }
+        Ok(())
+    }
+    
+    /// Asks for confirmation before writing `action` to `bot`'s cron entry
+    fn request_action(&mut self, action: BotAction, bot: BotSettings) {
+        if !self.allow_writes {
+            self.action_status = Some(Err("writes are disabled, start botmon with --allow-writes".to_string()));
+        } else if self.action_task.is_some() {
+            self.action_status = Some(Err("the last write is still running".to_string()));
+        } else {
+            self.pending_action = Some(PendingAction { action, bot, checkpoint: None });
+        }
+    }
+    
+    /// Opens the checkpoint editor for `bot`, as long as writes are allowed on this bus
+    fn open_checkpoint_editor(&mut self, bot: BotSettings) {
+        let bus = self.selected_bus.clone().unwrap_or_default();
+        let production = self.loaded_config.as_ref().is_some_and(|a| a.is_production(&bus));
+        if !self.allow_writes {
+            self.action_status = Some(Err("writes are disabled, start botmon with --allow-writes".to_string()));
+        } else if production && !self.allow_prod_checkpoints {
+            self.action_status = Some(Err(format!("refusing to move checkpoints on production bus {bus}, start botmon with --allow-prod-checkpoints")));
+        } else if self.action_task.is_some() {
+            self.action_status = Some(Err("the last write is still running".to_string()));
+        } else {
+            match CheckpointEditor::new(bot, &bus, self.bot_page.all_bots.as_deref().unwrap_or_default(), &self.bot_page.stats, &self.bot_page.queue_records) {
+                Ok(editor) => {
+                    self.checkpoint = Some(editor);
+                    self.mode = AppTab::Checkpoint;
+                },
+                Err(e) => self.action_status = Some(Err(e.to_string())),
+            }
+        }
+    }
+    
+    /// Handles keys in the checkpoint editor. The previous checkpoint is saved as an undo record before anything is written
+    fn checkpoint_key(&mut self, key_event: KeyEvent) {
+        let Some(editor) = self.checkpoint.as_mut() else {
+            return;
+        };
+        if key_event.code == KeyCode::Tab {
+            if editor.back() {
+                self.checkpoint = None;
+                self.mode = AppTab::BotView;
+            }
+            return;
+        }
+        let Some(pending) = editor.handle_key(key_event, &self.audit_log) else {
+            return;
+        };
+        let Some(change) = pending.checkpoint.clone() else {
+            return;
+        };
+        
+        let undo = UndoRecord {
+            pending: true,
+            ..UndoRecord::new(&editor.bus, &pending.bot.id, change, Utc::now().timestamp_millis())
+        };
+        match self.audit_log.record_undo(&undo) {
+            Ok(()) => {
+                self.start_action(pending);
+                self.checkpoint = None;
+                self.mode = AppTab::BotView;
+            },
+            Err(e) => editor.error = Some(format!("nothing was written, the undo record couldn't be saved: {e:#}")),
+        }
+    }
+    
+    /// Handles keys in the instance log pane, Tab closes it
+    fn log_key(&mut self, key_event: KeyEvent) {
+        let Some(view) = self.bot_page.selected_bot.as_mut() else {
+            return;
+        };
+        match (key_event.code, view.logs.as_mut()) {
+            (KeyCode::Tab, _) | (_, None) => {
+                view.logs = None;
+                self.mode = AppTab::BotView;
+            },
+            (_, Some(logs)) => logs.handle_key(key_event),
+        }
+    }
+    
+    /// Spawns a confirmed write, the result is picked up and audited in `check_refresh`
+    fn start_action(&mut self, pending: PendingAction) {
+        if let Some(source) = self.source.as_ref() {
+            self.action_status = None;
+            self.action_task = Some(spawn_bot_action(source.clone(), pending, Utc::now().timestamp_millis(), self.load_tx.clone()));
+        }
+    }
+    
+    /// Spawns a load of the selected bus, the results are picked up in `check_refresh`
+    fn start_load(&mut self) {
+        if let Some(source) = self.source.as_ref() {
+            self.load_progress = LoadProgress::default();
+            self.refresh_task = Some(spawn_load(source.clone(), self.load_tx.clone()));
+        }
+    }
+    
+    /// Spawns a fetch of the viewed bot's history, the results are picked up in `check_refresh`
+    fn fetch_bot_history(&mut self) {
+        if let (Some(source), Some(view)) = (self.source.as_ref(), self.bot_page.selected_bot.as_ref()) {
+            if let Some(task) = self.history_task.take() {
+                task.abort();
+            }
+            self.history_task = Some(spawn_bot_history(source.clone(), view.setting.id.clone(), view.range, self.load_tx.clone()));
+        }
+    }
+    
+    /// Spawns a fetch of every bot's stats for the queue view's range, the results are picked up in `check_refresh`
+    fn fetch_range_stats(&mut self) {
+        if let Some(source) = self.source.as_ref() {
+            if let Some(task) = self.range_task.take() {
+                task.abort();
+            }
+            self.range_task = Some(spawn_range_stats(source.clone(), self.queue_page.range, self.load_tx.clone()));
+        }
+    }
+    
+    /// Stops the initial load and goes back to choosing a bus
+    fn cancel_load(&mut self) {
+        if let Some(task) = self.refresh_task.take() {
+            task.abort();
+        }
+        // Drop anything the aborted load already sent
+        while self.load_rx.try_recv().is_ok() {}
+        
+        self.load_progress = LoadProgress::default();
+        self.selected_bus = None;
+        self.loaded_config = None;
+        self.source = None;
+        self.mode = AppTab::BusSelect;
+    }
+    
+    pub fn is_refreshing(&self) -> bool {
+        self.refresh_task.is_some()
+    }
+    
     fn render_frame(&mut self, frame: &mut Frame) {
         render_ui(frame, self)
         // frame.render_widget(self, frame.size());
@@ -83,23 +486,52 @@ impl AppState {
     }
     
     async fn handle_key_event(&mut self, key_event: KeyEvent) -> color_eyre::Result<()> {
-        
+        // The confirmation dialog takes every key, only `y` goes through with the write
+        if let Some(pending) = self.pending_action.take() {
+            if key_event.code == KeyCode::Char('y') {
+                self.start_action(pending);
+            }
+            return Ok(());
+        }
+        // So is the checkpoint editor, bot ids and event ids have to be typed in
+        if self.mode == AppTab::Checkpoint {
+            self.checkpoint_key(key_event);
+            return Ok(());
+        }
+        // And the log pane, whatever's typed searches the log
+        if self.mode == AppTab::BotLog {
+            self.log_key(key_event);
+            return Ok(());
+        }
+        // And the event browser while a position is being typed in
+        if self.mode == AppTab::Events && self.events.as_ref().is_some_and(|a| a.jump.is_some()) {
+            self.events_key(key_event);
+            return Ok(());
+        }
         
         match key_event.code {
-            KeyCode::Char('q') | KeyCode::Esc => Ok(self.exit()),
-            KeyCode::Home => Ok(self.return_home()),
+            KeyCode::Esc if self.mode == AppTab::Loading => {
+                self.cancel_load();
+                Ok(())
+            },
+            // Searches and filters take 'q' as text, only Esc quits from them
+            KeyCode::Char('q') | KeyCode::Esc if key_event.code == KeyCode::Esc || !self.mode.takes_text() => {
+                self.exit();
+                Ok(())
+            },
+            // There is no main menu until a bus has finished loading
+            KeyCode::Home if matches!(self.mode, AppTab::Loading | AppTab::BusSelect) => Ok(()),
+            KeyCode::Home => {
+                self.return_home();
+                Ok(())
+            },
             // KeyCode::Left => self.decrement_count()?,
             // KeyCode::Right => self.increment_count()?,
             _ => {
                 
                 
                 match self.mode {
-                    AppTab::Loading => {
-                        if self.bot_page.all_bots.as_ref().is_some_and(|a|!a.is_empty()) && !self.bot_page.stats.is_empty() {
-                            self.mode = AppTab::Main;
-                        }
-                        Ok(())
-                    }
+                    AppTab::Loading => Ok(()),
                     AppTab::Main => self.navigate(key_event),
                     AppTab::Bot => {
                         let list_len =  if self.bot_page.search_results.is_empty() {
@@ -127,6 +559,8 @@ impl AppState {
                                     self.bot_page.search.reset();
                                     self.bot_page.search_results.clear();
                                     self.bot_page.get_bot_details()?;
+                                    self.fetch_bot_history();
+                                    self.bot_view_parent = AppTab::Bot;
                                     self.mode = AppTab::BotView;
                                 }
                             }
@@ -169,24 +603,12 @@ impl AppState {
                                     self.selected_bus = Some(self.bus_select.buses[self.bus_select.bus_selected_index].clone());
                                     if let Some(selected_bus) = self.selected_bus.as_ref() {
                                         self.loaded_config = self.buses.get(selected_bus).cloned();
-                                        //TODO: now we grab the data from db
-                                        self.load_bot_data().await?;
-                                        self.mode = AppTab::Main;
-                                        // self.mode = AppTab::Loading;
-                                        // let client = self.client.clone();
-                                        // let stats_table = self.loaded_config.as_ref().unwrap().leo_stats.clone();
-                                        // let cron_table = self.loaded_config.as_ref().unwrap().leo_cron.clone();
-                                        // let bucket = AllBucketsBuilder::new(Period::Minute15)
-                                        //         .past_ms(Duration::days(1))
-                                        //         .build();
-                                        // let mut tasks = vec![];
-                                        
-                                        
-                                        // tasks.push(tokio::spawn(async move {
-                                            
-                                        //     get_all_bot_stats_for_period(client, stats_table, bucket).await
-                                        // }).await?);
-                                        // tasks.push(tokio::sp)
+                                        if let Some(config) = self.loaded_config.as_ref() {
+                                            self.bot_page.thresholds = config.health.clone();
+                                            self.source = Some(Arc::new(DynamoSource::connect(config.clone()).await));
+                                        }
+                                        self.mode = AppTab::Loading;
+                                        self.start_load();
                                     }
                                 }
                             }
@@ -196,7 +618,134 @@ impl AppState {
                         }
                         Ok(())
                     }
-                    AppTab::Queue => todo!(),
+                    AppTab::Queue => {
+                        let list_len = self.queue_page.search_results.len();
+
+                        match key_event.code {
+                            KeyCode::Down => {
+                                if list_len > 0 {
+                                    let index  = self.queue_page.current_select_index + list_len;
+                                    self.queue_page.current_select_index = index.saturating_add(1) % list_len;
+                                }
+                            },
+                            KeyCode::Up => {
+                                if list_len > 0 {
+                                    let index  = self.queue_page.current_select_index + list_len;
+                                    self.queue_page.current_select_index = index.saturating_sub(1) % list_len;
+                                }
+                            },
+                            KeyCode::Enter => {
+                                if list_len > 0 {
+                                    self.queue_page.selected_queue_name = Some(self.queue_page.search_results[self.queue_page.current_select_index].clone());
+                                    self.queue_page.get_queue_details(&self.bot_page.stats)?;
+                                    self.mode = AppTab::QueueView;
+                                }
+                            }
+                            _ => {
+                                // Do the search
+                                self.queue_page.search.handle_event(&Event::Key(key_event));
+                                self.queue_page.current_select_index = 0;
+                                self.queue_page.search_queues()
+                            }
+                        }
+                        Ok(())
+                    },
+                    AppTab::Overview => {
+                        let list_len = self.overview_page.visible.len();
+
+                        match key_event.code {
+                            KeyCode::Down => {
+                                if list_len > 0 {
+                                    let index  = self.overview_page.current_select_index + list_len;
+                                    self.overview_page.current_select_index = index.saturating_add(1) % list_len;
+                                }
+                            },
+                            KeyCode::Up => {
+                                if list_len > 0 {
+                                    let index  = self.overview_page.current_select_index + list_len;
+                                    self.overview_page.current_select_index = index.saturating_sub(1) % list_len;
+                                }
+                            },
+                            KeyCode::Left | KeyCode::Right => {
+                                let column = self.overview_page.sort.cycle(key_event.code == KeyCode::Left);
+                                self.overview_page.sort_by(column);
+                            },
+                            KeyCode::Tab => {
+                                let column = self.overview_page.sort;
+                                self.overview_page.sort_by(column);
+                            },
+                            KeyCode::Enter => {
+                                if let Some(row) = self.overview_page.selected() {
+                                    self.bot_page.selected_bot_name = Some(row.name.clone());
+                                    self.bot_page.get_bot_details()?;
+                                    self.fetch_bot_history();
+                                    self.bot_view_parent = AppTab::Overview;
+                                    self.mode = AppTab::BotView;
+                                }
+                            }
+                            _ => {
+                                // Everything else edits the filter
+                                self.overview_page.filter.handle_event(&Event::Key(key_event));
+                                self.overview_page.update_visible();
+                            }
+                        }
+                        Ok(())
+                    },
+                    AppTab::Topology => match self.topology.as_mut() {
+                        Some(topology) => match key_event.code {
+                            KeyCode::Tab => {
+                                self.mode = self.topology_parent;
+                                Ok(())
+                            }
+                            _ => topology.navigate(key_event),
+                        },
+                        None => bail!("cannot navigate a topology that wasn't built"),
+                    },
+                    // Keys are handled before the global ones
+                    AppTab::Checkpoint | AppTab::BotLog => Ok(()),
+                    AppTab::Events => {
+                        self.events_key(key_event);
+                        Ok(())
+                    },
+                    AppTab::Entries => self.entries_key(key_event),
+                    AppTab::Alerts => {
+                        match key_event.code {
+                            KeyCode::Up => self.alerts.vertical_scroll = self.alerts.vertical_scroll.saturating_sub(1),
+                            KeyCode::Down => self.alerts.vertical_scroll = self.alerts.vertical_scroll.saturating_add(1),
+                            _ => {},
+                        }
+                        Ok(())
+                    },
+                    AppTab::QueueView => match &mut self.queue_page.selected_queue {
+                        Some(queue_view_state) => {
+                            match key_event.code {
+                                KeyCode::Tab => {
+                                    self.mode = AppTab::Queue;
+                                    Ok(())
+                                }
+                                KeyCode::Char('t') => {
+                                    let focus = Node::Queue(queue_view_state.name.clone());
+                                    self.open_topology(focus);
+                                    Ok(())
+                                }
+                                KeyCode::Char('e') => {
+                                    let queue = queue_view_state.name.clone();
+                                    self.open_events(&queue, StreamPosition::End, None);
+                                    Ok(())
+                                }
+                                KeyCode::Char(a @ ('p' | 'P')) => {
+                                    let range = self.queue_page.range.cycle(a == 'P');
+                                    self.queue_page.set_range(range, &self.bot_page.stats);
+                                    if self.queue_page.needs_range_stats() {
+                                        self.fetch_range_stats();
+                                    }
+                                    Ok(())
+                                }
+                                _ => queue_view_state.navigate(key_event),
+                            }
+                        },
+                        None => bail!("cannot navigate a non-existant queue; \n{self:#?}"),
+                    },
                     AppTab::BotView => match &mut self.bot_page.selected_bot {
                         Some(bot_view_state) => {
                             match key_event.code {
@@ -209,11 +758,50 @@ impl AppState {
                                     bot_view_state.vertical_scroll_state = bot_view_state.vertical_scroll_state.position(bot_view_state.vertical_scroll);
                                 },
                                 KeyCode::Tab => {
-                                    self.mode = AppTab::Bot
+                                    self.mode = self.bot_view_parent
                                 }
-                                a => {
-                                    bail!("invalid key {a:?} pressed");
+                                KeyCode::Char('t') => {
+                                    let focus = Node::Bot(bot_view_state.setting.id.replace("bot:", ""));
+                                    self.open_topology(focus);
                                 }
+                                KeyCode::Char(a @ ('p' | 'P')) => {
+                                    bot_view_state.set_range(bot_view_state.range.cycle(a == 'P'));
+                                    self.fetch_bot_history();
+                                }
+                                KeyCode::Char('s') => {
+                                    let bot = bot_view_state.setting.clone();
+                                    self.request_action(BotAction::toggle_pause(&bot), bot);
+                                }
+                                KeyCode::Char('i') => {
+                                    let bot = bot_view_state.setting.clone();
+                                    self.request_action(BotAction::Trigger, bot);
+                                }
+                                KeyCode::Char('c') => {
+                                    let bot = bot_view_state.setting.clone();
+                                    self.open_checkpoint_editor(bot);
+                                }
+                                KeyCode::Left | KeyCode::Right => bot_view_state.inspector.select(key_event.code == KeyCode::Left),
+                                KeyCode::Enter => bot_view_state.inspector.toggle_section(),
+                                KeyCode::Char('r') => {
+                                    bot_view_state.inspector.raw = !bot_view_state.inspector.raw;
+                                    bot_view_state.vertical_scroll = 0;
+                                    bot_view_state.vertical_scroll_state = bot_view_state.vertical_scroll_state.position(0);
+                                }
+                                KeyCode::Char('e') => {
+                                    let (bot_id, lags) = (bot_view_state.setting.id.clone(), bot_view_state.read_lags.clone());
+                                    self.open_bot_events(&bot_id, &lags);
+                                }
+                                KeyCode::Char('y') => {
+                                    match bot_view_state.setting.systems().into_iter().next() {
+                                        Some(system) => self.open_entries(EntryTable::System, Some(system)),
+                                        None => self.action_status = Some(Err(format!("{} doesn't reference any systems", bot_view_state.setting.id))),
+                                    }
+                                }
+                                KeyCode::Char('l') => {
+                                    bot_view_state.logs = Some(LogPaneState::new(&bot_view_state.setting));
+                                    self.mode = AppTab::BotLog;
+                                }
+                                _ => {}
                             }
                             Ok(())
                         },
@@ -228,61 +816,20 @@ impl AppState {
         self.exit = true
     }
     
-    
-    
-    pub fn gen_test_data(&mut self) {
-        let mut data = vec![];
-        let past_time = Utc::now() - Duration::minutes(200);
-        for i in 0..200 {
-            let num_executions = fastrand::u32(1..=3000) as f64;
-            if i > 0 {
-                data.push(((past_time + Duration::minutes(i as i64)).timestamp() as f64, num_executions))
-            } else {
-                data.push((past_time.timestamp() as f64, num_executions))
-            }
-        }
-        
-        self.chart_data = data
-    
-    }
-    pub fn get_chart_data(&self) -> &[(f64, f64)] {
-        &self.chart_data
-    }
-
-    async fn load_bot_data(&mut self) -> color_eyre::Result<()> {
-   
-        // Get the bot stats
-        let bucket = AllBucketsBuilder::new(Period::Minute15)
-            .past_ms(Duration::days(1))
-            .build();
-
-        let bots = get_all_bot_stats_for_period(&self.client, &self.loaded_config.as_ref().unwrap().leo_stats, bucket).await?;
-        self.bot_page.stats = bots;
-
-        let bot_settings = get_all_bot_details(&self.client, &self.loaded_config.as_ref().unwrap().leo_cron).await?;
-        self.bot_page.all_bots = Some(bot_settings);
-
-        self.bot_page.bot_names();
-
-        Ok(())
-    }
-
     pub async fn new(params: &AppParams) -> color_eyre::Result<Self> {
         let refresh_rate = params.refresh_time;
         let refresh_at = Instant::now() + refresh_rate.to_std()?;
         
-        let buses = match &params.config_path {
-            Some(path) => {
-                let leo_string = read_to_string(path)?;
-                let config: HashMap<String, LeoConfig> = serde_json::from_str(&leo_string)?;
-                config
-            },
-            None => {
-                let leo_string = read_to_string("./config.json").wrap_err_with(||current_dir().unwrap().to_str().unwrap().to_string())?;
-                let config: HashMap<String, LeoConfig> = serde_json::from_str(&leo_string)?;
-                config
-            }
+        if params.fixtures.is_some() && params.bus.is_some() {
+            bail!("--fixtures and --bus can't be used together");
+        }
+        
+        // Fixtures don't need a leo config, but any buses in it can still be picked after cancelling
+        let mut buses = match params.fixtures {
+            Some(_) => load_buses(params.config_path.as_deref()).unwrap_or_default(),
+            None => load_buses(params.config_path.as_deref())?,
         };
+        override_endpoint(&mut buses, params.endpoint_url.as_deref());
         
         let selected_bus;
         let loaded_bus = match &params.bus {
@@ -294,45 +841,88 @@ impl AppState {
                 }
             },
             None => {
-                selected_bus = None;
+                selected_bus = params.fixtures.clone();
                 None
             }
         };
-        let config: aws_config::SdkConfig = aws_config::load_from_env().await;
-        let client = Client::new(&config);
+        
+        let source: Option<Arc<dyn BotmonSource>> = match (params.fixtures.as_ref(), loaded_bus.as_ref()) {
+            (Some(dir), _) => Some(Arc::new(FileSource::open(dir, true)?)),
+            (None, Some(bus_config)) => Some(Arc::new(DynamoSource::connect(bus_config.clone()).await)),
+            (None, None) => None,
+        };
 
-        let mode = if loaded_bus.is_some() {
-            AppTab::Main
+        let (load_tx, load_rx) = unbounded_channel();
+
+        // A bus or fixtures passed in on the command line start loading straight away
+        let mode = if source.is_some() {
+            AppTab::Loading
         } else {
             AppTab::BusSelect
         };
         
 
-        Ok(Self {
+        let mut app = Self {
             start_time: Instant::now(),
             mode,
             tab_index: 0,
-            chart_data: vec![],
-            bot_page: BotPageState::default(),
+            bot_page: BotPageState {
+                thresholds: loaded_bus.as_ref().map(|a| a.health.clone()).unwrap_or_default(),
+                ..BotPageState::default()
+            },
+            queue_page: QueuePageState::default(),
+            overview_page: OverviewPageState::default(),
+            topology: None,
+            topology_parent: AppTab::Main,
+            alerts: AlertState::new(AlertConfig::load(params.alerts.as_deref(), params.config_path.as_deref())?),
+            bot_view_parent: AppTab::Bot,
+            events: None,
+            events_parent: AppTab::QueueView,
+            entries: None,
+            entries_parent: AppTab::Main,
+            allow_writes: params.allow_writes,
+            allow_prod_checkpoints: params.allow_prod_checkpoints,
+            checkpoint: None,
+            audit_log: AuditLog::new(params.audit_log.as_deref(), params.config_path.as_deref()),
+            pending_action: None,
+            action_status: None,
             refresh_at,
             refresh_rate,
+            last_refresh: None,
+            refresh_error: None,
+            refresh_warning: None,
+            load_progress: LoadProgress::default(),
+            last_load: None,
+            refresh_task: None,
+            history_task: None,
+            range_task: None,
+            action_task: None,
+            events_task: None,
+            entries_task: None,
+            load_tx,
+            load_rx,
             exit: false,
             bus_select: BusSelectState::new(&buses),
             buses,
             selected_bus,
             loaded_config: loaded_bus,
-            aws_config: config,
-            client,
+            source,
             throbber_state: ThrobberState::default(),
             tick_rate: Duration::milliseconds(250),
-        })
+        };
+        
+        if app.mode == AppTab::Loading {
+            app.start_load();
+        }
+        
+        Ok(app)
     }
     
 }
 
 impl Navigate for AppState {
     fn navigate(&mut self, key_event: KeyEvent) -> color_eyre::Result<()> {
-        const TAB_SIZE: usize = 2;
+        const TAB_SIZE: usize = 6;
         match key_event.code {
             KeyCode::Down => {
                 let index = self.tab_index + TAB_SIZE;
@@ -342,7 +932,15 @@ impl Navigate for AppState {
                 let index = self.tab_index + TAB_SIZE;
                 self.tab_index = index.saturating_sub(1) % TAB_SIZE;
             }
-            KeyCode::Enter => self.mode = self.tab_index.into(),
+            KeyCode::Enter if self.tab_index == 4 => self.open_entries(EntryTable::System, None),
+            KeyCode::Enter if self.tab_index == 5 => self.open_entries(EntryTable::Settings, None),
+            KeyCode::Enter => {
+                self.mode = self.tab_index.into();
+                if self.mode == AppTab::Queue {
+                    self.queue_page.queue_names(&self.bot_page.stats);
+                    self.queue_page.search_queues();
+                }
+            },
             a => bail!("invalid key_code : {a:?}"),
         }
         Ok(())
@@ -351,21 +949,38 @@ impl Navigate for AppState {
 
 
 /// Control's which page that will show
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Default, Clone, Copy)]
 pub enum AppTab {
+    #[default]
     Main,
     BusSelect,
     Bot,
     Queue,
+    Overview,
+    Alerts,
+    Topology,
+    Checkpoint,
     BotView,
+    /// The bot view with its instance logs open
+    BotLog,
+    /// The events on a queue from the stream table
+    Events,
+    /// The system or settings table
+    Entries,
+    QueueView,
     Loading,
 }
 
 impl AppTab {
+    /// Pages where whatever's typed goes into a search or filter
+    pub fn takes_text(&self) -> bool {
+        matches!(self, AppTab::Bot | AppTab::Queue | AppTab::Overview | AppTab::Entries)
+    }
+    
     pub fn get_keys(&self) -> Vec<(&str, &str)>{
         let mut keys = vec![
-            ("Home", "Main Menu"), 
-            ("Esc|Q", "Quit")
+            ("Home", "Menu"), 
+            (if self.takes_text() { "Esc" } else { "Esc|Q" }, "Quit")
         ];
         
         match self {
@@ -376,17 +991,85 @@ impl AppTab {
                 // ("Home", "Main Menu"),
                 // ("Esc", "Quit")
             ]),
+            AppTab::Overview => keys.append(&mut vec![
+                ("↑", "Up"),
+                ("↓", "Down"),
+                ("←|→", "Sort Column"),
+                ("Tab", "Sort Order"),
+                ("Enter", "Select"),
+            ]),
+            AppTab::Topology => keys.append(&mut vec![
+                ("↑|↓", "Select"),
+                ("←|→", "Upstream/Downstream"),
+                ("Enter", "Focus"),
+                ("Backspace", "Previous"),
+                ("Tab", "Back"),
+            ]),
+            AppTab::Checkpoint => keys = vec![
+                ("↑|↓", "Queue"),
+                ("Enter", "Next"),
+                ("Tab", "Back"),
+            ],
+            AppTab::Alerts => keys.append(&mut vec![
+                ("↑", "Scroll Up"),
+                ("↓", "Scroll Down"),
+            ]),
             AppTab::BotView => keys.append(&mut vec![
+                ("↑|↓", "Scroll"),
+                ("←|→", "Section"),
+                ("Enter", "Fold"),
+                ("r", "Raw"),
+                ("p|P", "Range"),
+                ("t", "Graph"),
+                ("s", "Pause"),
+                ("i", "Run"),
+                ("c", "Ckpt"),
+                ("l", "Logs"),
+                ("e", "Events"),
+                ("y", "Sys"),
+                ("Tab", "Back"),
+            ]),
+            AppTab::Events => keys.append(&mut vec![
+                ("↑|↓", "Select"),
+                ("←|→", "Older/Newer"),
+                ("PgUp|PgDn", "Payload"),
+                ("s|e", "Oldest/Newest"),
+                ("g", "Go To"),
+                ("r", "Reload"),
+                ("Tab", "Back"),
+            ]),
+            AppTab::Entries => keys.append(&mut vec![
+                ("↑|↓", "Select"),
+                ("PgUp|PgDn", "Scroll"),
+                ("Type", "Search"),
+                ("←|→", "Linked Bot"),
+                ("Enter", "Open Bot"),
+                ("Tab", "Back"),
+            ]),
+            AppTab::BotLog => keys = vec![
+                ("←|→", "Instance"),
+                ("↑|↓|PgUp|PgDn", "Scroll"),
+                ("Type", "Search"),
+                ("Enter", "Next Match"),
+                ("Tab", "Close"),
+            ],
+            AppTab::QueueView => keys.append(&mut vec![
                 ("↑", "Scroll Up"),
                 ("↓", "Scroll Down"),
+                ("p|P", "Range"),
+                ("t", "Graph"),
+                ("e", "Events"),
                 ("Tab", "Back")
                 // ("Home", "Main Menu"),
                 // ("Esc", "Quit")
             ]),
-            AppTab::Loading => {}
+            AppTab::Loading => keys = vec![
+                ("Esc", "Cancel"),
+                ("Q", "Quit")
+            ],
         }
         
-        return keys;
+        keys
     }
 }
 
@@ -396,18 +1079,16 @@ impl From<usize> for AppTab {
             Self::Bot
         } else if value == 1 {
             Self::Queue
+        } else if value == 2 {
+            Self::Overview
+        } else if value == 3 {
+            Self::Alerts
         } else {
             Self::Main
         }
     }
 }
 
-impl Default for AppTab {
-    fn default() -> Self {
-        Self::Main
-    }
-}
-
 pub trait Page {
     fn get_keys(&self) -> Vec<(&str, &str)>;
 }
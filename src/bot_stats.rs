@@ -1,24 +1,17 @@
-use std::{cmp::{max, min}, collections::HashMap};
+use std::{cmp::{max, min}, collections::{BTreeMap, HashMap}};
 
 use serde::{Deserialize, Serialize};
 
-use crate::dynamo::Period;
-
-#[derive(Deserialize, Debug)]
-pub struct BotStats {
-    current: ExecutionStats,
-}
 #[derive(Deserialize, Debug, Default, Serialize, Clone)]
 pub struct QueueStats {
     pub checkpoint: Option<String>,
     pub source_timestamp: i64,
     pub timestamp: i64,
-    pub units: u32,
+    pub units: u64,
 }
 
 impl QueueStats {
     pub fn merge(&mut self, other: &Self) {
-        println!("got to merge");
         self.source_timestamp = max(self.source_timestamp, other.source_timestamp);
         self.timestamp = max(self.timestamp, other.timestamp);
         self.units += other.units;
@@ -55,38 +48,38 @@ pub struct BaseExecutionStats {
 
 #[derive(Deserialize, Debug, Serialize)]
 pub struct Stats {
-    completions: u32,
-    duration: u32,
-    errors: u32,
-    max_duration: u32,
-    min_duration: u32,
-    units: u32
+    pub completions: u64,
+    pub duration: u64,
+    pub errors: u64,
+    pub max_duration: u32,
+    pub min_duration: u32,
+    pub units: u64
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CondensedStats {
-    execution_stats: Stats,
-    read: HashMap<String, QueueStats>,
-    write: HashMap<String, QueueStats>,
+    pub execution_stats: Stats,
+    pub read: HashMap<String, QueueStats>,
+    pub write: HashMap<String, QueueStats>,
 }
 
 impl CondensedStats {
     pub fn merge_execution_stats(&mut self, other: &BotDynamoStatsRecord) {
         if let Some(other_execution_stats) = other.current.execution.as_ref() {
             if let Some(o_completions) = other_execution_stats.completions {
-                self.execution_stats.completions += o_completions;
+                self.execution_stats.completions += u64::from(o_completions);
             }
             if let Some(o_units) = other_execution_stats.units {
-                self.execution_stats.units += o_units;
+                self.execution_stats.units += u64::from(o_units);
             }
             if let Some(o_duration) = other_execution_stats.duration {
-                self.execution_stats.duration += o_duration;
+                self.execution_stats.duration += u64::from(o_duration);
             }
             if let Some(o_max_duration) = other_execution_stats.max_duration {
                 self.execution_stats.max_duration = max(self.execution_stats.max_duration, o_max_duration);
             }
             if let Some(o_errors) = other_execution_stats.errors {
-                self.execution_stats.errors += o_errors;
+                self.execution_stats.errors += u64::from(o_errors);
             }
             
             if let Some(o_min_duration) = other_execution_stats.min_duration {
@@ -101,8 +94,6 @@ impl CondensedStats {
     }
     
     pub fn merge_stats(&mut self, other_read: &StatsOrEmpty, other_write: &StatsOrEmpty) {
-        println!("got to merge_stats");
-        
         // Merge per queue as we will want reporting per queue
         if let StatsOrEmpty::NotEmpty(o_read) = other_read {
             
@@ -163,22 +154,64 @@ pub fn merge_bot_stats(bot_stats: &[BotDynamoStatsRecord]) -> CondensedStats {
    };
    
    for stats in bot_stats {
-       stat.merge_execution_stats(&stats)
+       stat.merge_execution_stats(stats)
    }
    stat
 }
 
+/// Per bucket time series of a bot's execution stats, ready to be charted.
+/// Each point is `(time in seconds, value)`
+#[derive(Debug, Default, Clone)]
+pub struct ExecutionSeries {
+    pub completions: Vec<(f64, f64)>,
+    pub errors: Vec<(f64, f64)>,
+    pub units: Vec<(f64, f64)>,
+    pub avg_duration: Vec<(f64, f64)>,
+}
+
+impl ExecutionSeries {
+    pub fn from_stats(bot_stats: &[BotDynamoStatsRecord]) -> Self {
+        // (completions, errors, units, duration) summed per bucket time, in u64 as a week of a busy bot's durations won't fit in u32
+        let mut buckets: BTreeMap<i64, (u64, u64, u64, u64)> = BTreeMap::new();
+        
+        for stats in bot_stats {
+            let bucket = buckets.entry(stats.time).or_default();
+            if let Some(execution) = stats.current.execution.as_ref() {
+                bucket.0 += u64::from(execution.completions.unwrap_or_default());
+                bucket.1 += u64::from(execution.errors.unwrap_or_default());
+                bucket.2 += u64::from(execution.units.unwrap_or_default());
+                bucket.3 += u64::from(execution.duration.unwrap_or_default());
+            }
+        }
+        
+        let mut series = Self::default();
+        for (time, (completions, errors, units, duration)) in buckets {
+            let seconds = (time / 1000) as f64;
+            series.completions.push((seconds, completions as f64));
+            series.errors.push((seconds, errors as f64));
+            series.units.push((seconds, units as f64));
+            let avg_duration = if completions > 0 {
+                duration as f64 / completions as f64
+            } else {
+                0.0
+            };
+            series.avg_duration.push((seconds, avg_duration));
+        }
+        
+        series
+    }
+}
 
 #[cfg(test)]
 mod bot_stats_tests {
-    use std::fs::{read_to_string, File};
+    use std::fs::read_to_string;
 
-    use super::{merge_bot_stats, BotDynamoStatsRecord};
+    use super::{merge_bot_stats, BotDynamoStatsRecord, ExecutionSeries};
 
     #[test]
     fn combinor_works() {
         
-        let bot_stats_raw = read_to_string("./bot_stats.json").unwrap();
+        let bot_stats_raw = read_to_string("./fixtures/bot_stats.json").unwrap();
         
         let stats: Vec<BotDynamoStatsRecord> = serde_json::from_str(&bot_stats_raw).unwrap();
         
@@ -186,4 +219,52 @@ mod bot_stats_tests {
         
         println!("{}", serde_json::to_string(&combined).unwrap());
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn execution_series_is_bucketed_by_time() {
+        let stats: Vec<BotDynamoStatsRecord> = serde_json::from_str(r#"[
+            {"id": "bot:a", "bucket": "b", "period": "minute_15", "time": 1800000, "current": {"execution": {"completions": 2, "duration": 300, "errors": 1, "max_duration": null, "min_duration": null, "units": 5}, "read": {}, "write": {}}},
+            {"id": "bot:a", "bucket": "a", "period": "minute_15", "time": 900000, "current": {"execution": {"completions": 4, "duration": 100, "errors": 0, "max_duration": null, "min_duration": null, "units": 8}, "read": {}, "write": {}}},
+            {"id": "bot:a", "bucket": "c", "period": "minute_15", "time": 2700000, "current": {"execution": null, "read": {}, "write": {}}}
+        ]"#).unwrap();
+        
+        let series = ExecutionSeries::from_stats(&stats);
+        
+        assert_eq!(series.completions, vec![(900.0, 4.0), (1800.0, 2.0), (2700.0, 0.0)]);
+        assert_eq!(series.errors, vec![(900.0, 0.0), (1800.0, 1.0), (2700.0, 0.0)]);
+        assert_eq!(series.units, vec![(900.0, 8.0), (1800.0, 5.0), (2700.0, 0.0)]);
+        assert_eq!(series.avg_duration, vec![(900.0, 25.0), (1800.0, 150.0), (2700.0, 0.0)]);
+    }
+    
+    #[test]
+    fn execution_series_sums_past_u32() {
+        let stats: Vec<BotDynamoStatsRecord> = serde_json::from_str(r#"[
+            {"id": "bot:a", "bucket": "a", "period": "day", "time": 86400000, "current": {"execution": {"completions": 2, "duration": 4000000000, "errors": 0, "max_duration": null, "min_duration": null, "units": 4000000000}, "read": {}, "write": {}}},
+            {"id": "bot:b", "bucket": "a", "period": "day", "time": 86400000, "current": {"execution": {"completions": 2, "duration": 4000000000, "errors": 0, "max_duration": null, "min_duration": null, "units": 4000000000}, "read": {}, "write": {}}}
+        ]"#).unwrap();
+        
+        let series = ExecutionSeries::from_stats(&stats);
+        
+        assert_eq!(series.units, vec![(86400.0, 8000000000.0)]);
+        assert_eq!(series.avg_duration, vec![(86400.0, 2000000000.0)]);
+    }
+
+    #[test]
+    fn merged_stats_sum_past_u32() {
+        let record = r#"{"id": "bot:a", "bucket": "a", "period": "day", "time": 86400000, "current": {
+            "execution": {"completions": 4000000000, "duration": 4000000000, "errors": 4000000000, "max_duration": null, "min_duration": null, "units": 4000000000},
+            "read": {"queue:orders": {"checkpoint": null, "source_timestamp": 0, "timestamp": 0, "units": 4000000000}},
+            "write": {"queue:orders_enriched": {"checkpoint": null, "source_timestamp": 0, "timestamp": 0, "units": 4000000000}}
+        }}"#;
+        let stats: Vec<BotDynamoStatsRecord> = serde_json::from_str(&format!("[{record}, {record}]")).unwrap();
+
+        let merged = merge_bot_stats(&stats);
+
+        assert_eq!(merged.execution_stats.completions, 8000000000);
+        assert_eq!(merged.execution_stats.duration, 8000000000);
+        assert_eq!(merged.execution_stats.errors, 8000000000);
+        assert_eq!(merged.execution_stats.units, 8000000000);
+        assert_eq!(merged.read["queue:orders"].units, 8000000000);
+        assert_eq!(merged.write["queue:orders_enriched"].units, 8000000000);
+    }
+}
@@ -0,0 +1,378 @@
+use std::io::{stdout, Write};
+
+use argh::FromArgs;
+use aws_sdk_dynamodb::Client;
+use chrono::Duration;
+use color_eyre::eyre::{bail, eyre};
+
+use crate::{bot_stats::{merge_bot_stats, BotDynamoStatsRecord}, dynamo::{AllBucketsBuilder, Period}, leo_config::{load_buses, override_endpoint, LeoConfig}, output::{write_many, write_one, BotStatsReport, QueueReport}, pages::{bot::BotSettings, queue::{queue_name, QueueViewState}}, seed::seed, source::{BotmonSource, DynamoSource, FileSource}, stream::{StreamPosition, PAGE_SIZE}, topology::{GraphFormat, Node, Topology}, AppParams};
+
+#[derive(FromArgs, Debug)]
+#[argh(subcommand)]
+pub enum Command {
+    Bots(BotsCommand),
+    Bot(BotCommand),
+    Queue(QueueCommand),
+    Topology(TopologyCommand),
+    Seed(SeedCommand),
+}
+
+#[derive(FromArgs, Debug)]
+/// work with every bot on the bus
+#[argh(subcommand, name = "bots")]
+pub struct BotsCommand {
+    #[argh(subcommand)]
+    pub command: BotsSubcommand,
+}
+
+#[derive(FromArgs, Debug)]
+#[argh(subcommand)]
+pub enum BotsSubcommand {
+    List(BotsListCommand),
+}
+
+#[derive(FromArgs, Debug)]
+/// list every bot on the bus
+#[argh(subcommand, name = "list")]
+pub struct BotsListCommand {}
+
+#[derive(FromArgs, Debug)]
+/// inspect a single bot
+#[argh(subcommand, name = "bot")]
+pub struct BotCommand {
+    #[argh(subcommand)]
+    pub command: BotSubcommand,
+}
+
+#[derive(FromArgs, Debug)]
+#[argh(subcommand)]
+pub enum BotSubcommand {
+    Show(BotShowCommand),
+    Stats(BotStatsCommand),
+}
+
+#[derive(FromArgs, Debug)]
+/// print a bot's settings from the cron table
+#[argh(subcommand, name = "show")]
+pub struct BotShowCommand {
+    #[argh(positional)]
+    /// the bot id, with or without the `bot:` prefix
+    pub id: String,
+}
+
+#[derive(FromArgs, Debug)]
+/// print a bot's stats merged over a window of time
+#[argh(subcommand, name = "stats")]
+pub struct BotStatsCommand {
+    #[argh(positional)]
+    /// the bot id, with or without the `bot:` prefix
+    pub id: String,
+
+    #[argh(option, default = "Period::Minute15")]
+    /// the stats period to query: minute, minute_5, minute_15, hour, day or week. Defaults to minute_15
+    pub period: Period,
+
+    #[argh(option, from_str_fn(parse_since), default = "Duration::days(1)")]
+    /// how far back to look, e.g. 90s, 30m, 6h, 7d or 2w. Defaults to 1d
+    pub since: Duration,
+}
+
+#[derive(FromArgs, Debug)]
+/// inspect a single queue
+#[argh(subcommand, name = "queue")]
+pub struct QueueCommand {
+    #[argh(subcommand)]
+    pub command: QueueSubcommand,
+}
+
+#[derive(FromArgs, Debug)]
+#[argh(subcommand)]
+pub enum QueueSubcommand {
+    Show(QueueShowCommand),
+    Events(QueueEventsCommand),
+}
+
+#[derive(FromArgs, Debug)]
+/// print the bots writing to and reading from a queue
+#[argh(subcommand, name = "show")]
+pub struct QueueShowCommand {
+    #[argh(positional)]
+    /// the queue name, with or without the `queue:` prefix
+    pub queue: String,
+
+    #[argh(option, default = "Period::Minute15")]
+    /// the stats period to query: minute, minute_5, minute_15, hour, day or week. Defaults to minute_15
+    pub period: Period,
+
+    #[argh(option, from_str_fn(parse_since), default = "Duration::days(1)")]
+    /// how far back to look, e.g. 90s, 30m, 6h, 7d or 2w. Defaults to 1d
+    pub since: Duration,
+}
+
+#[derive(FromArgs, Debug)]
+/// print a page of the events on a queue from the stream table
+#[argh(subcommand, name = "events")]
+pub struct QueueEventsCommand {
+    #[argh(positional)]
+    /// the queue name, with or without the `queue:` prefix
+    pub queue: String,
+
+    #[argh(option)]
+    /// where to start: an event id, a time like "2024-07-15 12:30", epoch millis, start, end or bot:<id> for that
+    /// bot's read checkpoint. Defaults to end, the newest events
+    pub from: Option<String>,
+
+    #[argh(option, default = "PAGE_SIZE")]
+    /// how many events to print. Defaults to 25
+    pub limit: usize,
+}
+
+#[derive(FromArgs, Debug)]
+/// print the graph of bots and the queues they read and write as dot or mermaid
+#[argh(subcommand, name = "topology")]
+pub struct TopologyCommand {
+    #[argh(option, default = "GraphFormat::Dot")]
+    /// the graph language to print: dot or mermaid. Defaults to dot
+    pub graph: GraphFormat,
+
+    #[argh(option)]
+    /// only print the bots and queues near this one, as `bot:<id>`, `queue:<name>` or a bare bot or queue name
+    pub around: Option<String>,
+
+    #[argh(option, default = "2")]
+    /// how many edges out from --around to go. Defaults to 2
+    pub hops: usize,
+
+    #[argh(option, default = "Period::Minute15")]
+    /// the stats period to count events over: minute, minute_5, minute_15, hour, day or week. Defaults to minute_15
+    pub period: Period,
+
+    #[argh(option, from_str_fn(parse_since), default = "Duration::days(1)")]
+    /// how far back to count events, e.g. 90s, 30m, 6h, 7d or 2w. Defaults to 1d
+    pub since: Duration,
+}
+
+#[derive(FromArgs, Debug)]
+/// create the cron, stats, event, stream, system and settings tables on a local endpoint and load fixture data into them
+#[argh(subcommand, name = "seed")]
+pub struct SeedCommand {
+    #[argh(option, default = "String::from(\"fixtures/bot_settings.json\")")]
+    /// json array of bot settings to load into the cron table. Defaults to fixtures/bot_settings.json
+    pub bots: String,
+
+    #[argh(option, default = "String::from(\"fixtures/bot_stats.json\")")]
+    /// json array of bot stats to load into the stats table. Defaults to fixtures/bot_stats.json
+    pub stats: String,
+
+    #[argh(option, default = "String::from(\"fixtures/leo_event.json\")")]
+    /// json array of queue records to load into the event table. Defaults to fixtures/leo_event.json
+    pub queues: String,
+
+    #[argh(option, default = "String::from(\"fixtures/leo_stream.json\")")]
+    /// json array of events to load into the stream table. Defaults to fixtures/leo_stream.json
+    pub events: String,
+
+    #[argh(option, default = "String::from(\"fixtures/leo_system.json\")")]
+    /// json array of systems to load into the system table. Defaults to fixtures/leo_system.json
+    pub systems: String,
+
+    #[argh(option, default = "String::from(\"fixtures/leo_settings.json\")")]
+    /// json array of settings to load into the settings table. Defaults to fixtures/leo_settings.json
+    pub settings: String,
+
+    #[argh(switch)]
+    /// load the stats with their original times instead of moving them up to now
+    pub keep_times: bool,
+}
+
+/// Parses a relative amount of time like `30m` or `6h`
+pub fn parse_since(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+
+    let amount: i64 = amount.parse().map_err(|_| format!("invalid duration '{value}', expected something like 30m or 6h"))?;
+
+    match unit {
+        "s" => Ok(Duration::seconds(amount)),
+        "m" => Ok(Duration::minutes(amount)),
+        "h" => Ok(Duration::hours(amount)),
+        "d" => Ok(Duration::days(amount)),
+        "w" => Ok(Duration::weeks(amount)),
+        a => Err(format!("invalid duration unit '{a}', expected one of s, m, h, d or w")),
+    }
+}
+
+/// Runs a headless command against the bus, printing the results to stdout
+pub async fn run(params: &AppParams, command: &Command) -> color_eyre::Result<()> {
+    let mut out = stdout().lock();
+
+    match command {
+        Command::Bots(BotsCommand { command: BotsSubcommand::List(_) }) => {
+            let source = headless_source(params).await?;
+            let (mut bots, _) = source.bot_settings(&mut |_| {}).await?;
+            bots.sort_by(|a, b| a.id.cmp(&b.id));
+            write_many(&mut out, params.format, &bots)?;
+        },
+        Command::Bot(BotCommand { command: BotSubcommand::Show(show) }) => {
+            let source = headless_source(params).await?;
+            let (bots, _) = source.bot_settings(&mut |_| {}).await?;
+            let bot = find_bot(&bots, &show.id)?;
+            write_one(&mut out, params.format, bot)?;
+        },
+        Command::Bot(BotCommand { command: BotSubcommand::Stats(stats) }) => {
+            let source = headless_source(params).await?;
+            let bot_id = bot_id(&stats.id);
+            let bucket = AllBucketsBuilder::new(stats.period)
+                .past_ms(stats.since)
+                .build();
+            let (records, _) = source.stats_for_bot(&bot_id, bucket).await?;
+            let merged = merge_bot_stats(&records);
+            write_one(&mut out, params.format, &BotStatsReport { bot: &bot_id, stats: &merged })?;
+        },
+        Command::Queue(QueueCommand { command: QueueSubcommand::Show(show) }) => {
+            let source = headless_source(params).await?;
+            let name = queue_name(&show.queue);
+            let records = query_stats(source.as_ref(), show.period, show.since).await?;
+            let (bots, _) = source.bot_settings(&mut |_| {}).await?;
+            let (queues, _) = source.queue_records(&mut |_| {}).await?;
+            let view = QueueViewState::new(&name, &records, &bots, &queues);
+            if view.writers.is_empty() && view.readers.is_empty() && view.record.is_none() {
+                bail!("no stats found for queue '{name}' in the last {} and it has no record in the event table", show.since);
+            }
+
+            write_one(&mut out, params.format, &QueueReport::new(&view))?;
+        },
+        Command::Queue(QueueCommand { command: QueueSubcommand::Events(events) }) => {
+            let source = headless_source(params).await?;
+            let position = match events.from.as_deref() {
+                None => StreamPosition::End,
+                Some(from) if from.starts_with("bot:") => {
+                    let (bots, _) = source.bot_settings(&mut |_| {}).await?;
+                    StreamPosition::parse(from, &events.queue, &bots).map_err(|e| eyre!(e))?
+                },
+                Some(from) => StreamPosition::parse(from, &events.queue, &[]).map_err(|e| eyre!(e))?,
+            };
+            let (page, _) = source.stream_events(&events.queue, &position, events.limit).await?;
+            if page.is_empty() {
+                bail!("no events found on queue '{}' {position}", queue_name(&events.queue));
+            }
+
+            write_many(&mut out, params.format, &page)?;
+        },
+        Command::Topology(command) => {
+            let source = headless_source(params).await?;
+            let records = query_stats(source.as_ref(), command.period, command.since).await?;
+            let (bots, _) = source.bot_settings(&mut |_| {}).await?;
+            let mut topology = Topology::build(&bots, &records);
+
+            let focus = command.around.as_deref()
+                .map(|id| find_node(&topology, id))
+                .transpose()?;
+            if let Some(node) = focus.as_ref() {
+                topology = topology.around(node, command.hops);
+            }
+            if topology.edges.is_empty() {
+                bail!("no bots or queues found on the bus");
+            }
+
+            match command.graph {
+                GraphFormat::Dot => write!(out, "{}", topology.to_dot(focus.as_ref()))?,
+                GraphFormat::Mermaid => write!(out, "{}", topology.to_mermaid(focus.as_ref()))?,
+            }
+        },
+        Command::Seed(seed_command) => {
+            if params.fixtures.is_some() {
+                bail!("seed writes to a bus's tables and can't be used with --fixtures");
+            }
+            let config = headless_bus(params)?;
+            let client = Client::new(&config.sdk_config().await);
+            let summary = seed(&client, &config, seed_command).await?;
+            writeln!(
+                out,
+                "seeded {} bots into {}, {} stats into {}, {} queues into {}, {} events into {}, {} systems into {} and {} settings into {}",
+                summary.bots, config.leo_cron, summary.stats, config.leo_stats, summary.queues, config.leo_event, summary.events, config.leo_stream,
+                summary.systems, config.leo_system, summary.settings, config.leo_settings,
+            )?;
+        },
+    }
+
+    Ok(())
+}
+
+/// Headless commands have no bus select screen so the bus has to be passed in
+fn headless_bus(params: &AppParams) -> color_eyre::Result<LeoConfig> {
+    let mut buses = load_buses(params.config_path.as_deref())?;
+    override_endpoint(&mut buses, params.endpoint_url.as_deref());
+    let Some(bus) = params.bus.as_ref() else {
+        bail!("--bus or --fixtures is required when running a command");
+    };
+
+    buses.get(bus).cloned().ok_or_else(|| eyre!("unable to find {bus} in leo config"))
+}
+
+/// Reads from the fixtures directory when one is passed, otherwise from the bus
+async fn headless_source(params: &AppParams) -> color_eyre::Result<Box<dyn BotmonSource>> {
+    match params.fixtures.as_ref() {
+        Some(dir) => Ok(Box::new(FileSource::open(dir, true)?)),
+        None => Ok(Box::new(DynamoSource::connect(headless_bus(params)?).await)),
+    }
+}
+
+async fn query_stats(source: &dyn BotmonSource, period: Period, since: Duration) -> color_eyre::Result<Vec<BotDynamoStatsRecord>> {
+    let bucket = AllBucketsBuilder::new(period)
+        .past_ms(since)
+        .build();
+    let (records, _) = source.stats_for_period(bucket, &mut |_| {}).await?;
+
+    Ok(records)
+}
+
+fn bot_id(id: &str) -> String {
+    if id.starts_with("bot:") {
+        id.to_owned()
+    } else {
+        format!("bot:{id}")
+    }
+}
+
+fn find_bot<'a>(bots: &'a [BotSettings], id: &str) -> color_eyre::Result<&'a BotSettings> {
+    let bot_id = bot_id(id);
+    bots.iter()
+        .find(|a| a.id == bot_id || a.id == id)
+        .ok_or_else(|| eyre!("unable to find bot '{id}'"))
+}
+
+/// Resolves a bot or queue in the graph, preferring a bot when a bare name matches both
+fn find_node(topology: &Topology, id: &str) -> color_eyre::Result<Node> {
+    let candidates = if let Some(name) = id.strip_prefix("bot:") {
+        vec![Node::Bot(name.to_owned())]
+    } else if id.starts_with("queue:") {
+        vec![Node::Queue(queue_name(id))]
+    } else {
+        vec![Node::Bot(id.to_owned()), Node::Queue(id.to_owned())]
+    };
+
+    candidates.into_iter()
+        .find(|a| topology.contains(a))
+        .ok_or_else(|| eyre!("unable to find bot or queue '{id}' on the bus"))
+}
+
+#[cfg(test)]
+mod cli_tests {
+    use chrono::Duration;
+
+    use super::parse_since;
+
+    #[test]
+    fn parse_since_works() {
+        assert_eq!(parse_since("90s"), Ok(Duration::seconds(90)));
+        assert_eq!(parse_since("30m"), Ok(Duration::minutes(30)));
+        assert_eq!(parse_since("6h"), Ok(Duration::hours(6)));
+        assert_eq!(parse_since("7d"), Ok(Duration::days(7)));
+        assert_eq!(parse_since("2w"), Ok(Duration::weeks(2)));
+        assert!(parse_since("6").is_err());
+        assert!(parse_since("h").is_err());
+        assert!(parse_since("6y").is_err());
+    }
+}
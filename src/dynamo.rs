@@ -1,15 +1,15 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{fmt::Display, str::FromStr};
 
-use aws_sdk_dynamodb::{types::AttributeValue, Client};
-use chrono::{DateTime, Duration, Utc};
+use aws_sdk_dynamodb::{types::{AttributeValue, ConsumedCapacity, ReturnConsumedCapacity}, Client};
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
 use color_eyre::eyre::{bail, Context};
 use serde::{Deserialize, Serialize};
 use serde_dynamo::from_item;
 use serde_json::Value;
 
-use crate::{bot_stats::BotDynamoStatsRecord, pages::bot::BotSettings};
+use crate::{actions::{BotAction, PendingAction}, bot_stats::BotDynamoStatsRecord, pages::{bot::BotSettings, entries::TableEntry, queue::{queue_name, QueueRecord}}, stream::{StreamEvent, StreamPosition}};
 
-#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
 #[serde(rename_all="snake_case")]
 pub enum Period {
     Minute,
@@ -43,11 +43,29 @@ impl Display for Period {
     }
 }
 
-#[derive(Debug)]
+impl FromStr for Period {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "minute" => Ok(Period::Minute),
+            "minute_5" => Ok(Period::Minute5),
+            "minute_15" => Ok(Period::Minute15),
+            "hour" => Ok(Period::Hour),
+            "day" => Ok(Period::Day),
+            "week" => Ok(Period::Week),
+            a => Err(format!("unknown period '{a}', expected one of minute, minute_5, minute_15, hour, day, week")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
 pub struct AllBuckets {
-    period: Period,
-    start: i64,
-    end: i64
+    pub period: Period,
+    /// Epoch millis
+    pub start: i64,
+    /// Epoch millis
+    pub end: i64
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -107,44 +125,113 @@ impl BotBucket {
 
 impl Display for BotBucket {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Buckets start on a multiple of their period, the same way the leo sdk writes them
+        let minute = self.date.minute();
         let date = match self.period {
-            Period::Minute | Period::Minute15 | Period::Minute5=> {
+            Period::Minute => {
                 self.date.format("%Y-%m-%d %H:%M").to_string()
             },
+            Period::Minute5 => {
+                format!("{} {:02}:{:02}", self.date.format("%Y-%m-%d"), self.date.hour(), minute - minute % 5)
+            },
+            Period::Minute15 => {
+                format!("{} {:02}:{:02}", self.date.format("%Y-%m-%d"), self.date.hour(), minute - minute % 15)
+            },
             Period::Hour => {
                 self.date.format("%Y-%m-%d %H").to_string()
             },
             Period::Day => {
                 self.date.format("%Y-%m-%d").to_string()
             },
-            Period::Week => unimplemented!("haven't found an instance of a week yet"),
+            // Weeks start on sunday
+            Period::Week => {
+                let days = self.date.weekday().num_days_from_sunday();
+                (self.date - Duration::days(days.into())).format("%Y-%m-%d").to_string()
+            },
         };
         
         write!(f, "{}_{date}", self.period)
     }
 }
 
-pub async fn get_all_bot_stats_for_period(client: &Client, table_name: &str, bucket: AllBuckets) -> color_eyre::Result<Vec<BotDynamoStatsRecord>> {
+/// A span of time to look back over and the stats period to read it at
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatsRange {
+    pub span: Duration,
+    pub period: Period,
+}
+
+impl StatsRange {
+    /// The ranges the bot and queue views can switch between
+    pub const PRESETS: [StatsRange; 5] = [
+        StatsRange { span: Duration::hours(1), period: Period::Minute },
+        StatsRange { span: Duration::hours(6), period: Period::Minute5 },
+        StatsRange { span: Duration::days(1), period: Period::Minute15 },
+        StatsRange { span: Duration::days(7), period: Period::Hour },
+        StatsRange { span: Duration::days(30), period: Period::Day },
+    ];
+    /// Every bot's stats are loaded over this range
+    pub const LOAD: StatsRange = Self::PRESETS[2];
+    /// The bot view starts on this range for the selected bot
+    pub const BOT_HISTORY: StatsRange = Self::PRESETS[3];
+    
+    /// The buckets covering this range up until now
+    pub fn bucket(&self) -> AllBuckets {
+        AllBucketsBuilder::new(self.period)
+            .past_ms(self.span)
+            .build()
+    }
+    
+    /// The next preset, or the previous one when going `back`, wrapping around at either end
+    pub fn cycle(&self, back: bool) -> Self {
+        let len = Self::PRESETS.len();
+        let index = Self::PRESETS.iter().position(|a| a == self).unwrap_or(0);
+        let index = if back { index + len - 1 } else { index + 1 };
+        Self::PRESETS[index % len]
+    }
+}
+
+impl Default for StatsRange {
+    fn default() -> Self {
+        Self::LOAD
+    }
+}
+
+impl Display for StatsRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.span.num_hours() % 24 == 0 {
+            write!(f, "{}d @ {}", self.span.num_days(), self.period)
+        } else {
+            write!(f, "{}h @ {}", self.span.num_hours(), self.period)
+        }
+    }
+}
+
+/// Running totals for a paginated query or scan
+#[derive(Debug, Default, Clone, Copy)]
+pub struct QuerySummary {
+    pub pages: usize,
+    pub items: usize,
+    /// Read capacity units consumed across every page
+    pub consumed_capacity: f64,
+}
+
+impl QuerySummary {
+    fn add_page(&mut self, items: usize, consumed_capacity: Option<&ConsumedCapacity>) {
+        self.pages += 1;
+        self.items += items;
+        self.consumed_capacity += consumed_capacity.and_then(|a| a.capacity_units()).unwrap_or_default();
+    }
+}
+
+/// Queries every bot's stats for the bucket's period and time range, following `LastEvaluatedKey` until
+/// every page has been read. `on_progress` is called after each page
+pub async fn get_all_bot_stats_for_period(client: &Client, table_name: &str, bucket: AllBuckets, mut on_progress: impl FnMut(&QuerySummary)) -> color_eyre::Result<(Vec<BotDynamoStatsRecord>, QuerySummary)> {
     // println!("bucket = {bucket:?}");
     let mut stats: Vec<BotDynamoStatsRecord> = vec![];
+    let mut summary = QuerySummary::default();
     
-   //  let query = {
-			// 	TableName: STATS_TABLE,
-			// 	IndexName: "period-time-index",
-			// 	KeyConditionExpression: "#period = :period and #time between :start and :end",
-			// 	ExpressionAttributeNames: {
-			// 		"#time": "time",
-			// 		"#period": "period"
-			// 	},
-			// 	ExpressionAttributeValues: {
-			// 		":start": span.start,
-			// 		":end": span.end,
-			// 		":period": period
-			// 	},
-			// 	"ReturnConsumedCapacity": 'TOTAL'
-			// };
-    
-    let output = client.query()
+    let mut pages = client.query()
         .table_name(table_name)
         .index_name("period-time-index")
         .key_condition_expression("#period = :period and #time between :start and :end")
@@ -153,99 +240,277 @@ pub async fn get_all_bot_stats_for_period(client: &Client, table_name: &str, buc
         .expression_attribute_values(":start", AttributeValue::N(bucket.start.to_string()))
         .expression_attribute_values(":end", AttributeValue::N(bucket.end.to_string()))
         .expression_attribute_values(":period", AttributeValue::S(bucket.period.to_string()))
-        .send().await.wrap_err_with(||format!("failed to get all stats|{table_name}|{bucket:?}"))?;
-    
-    if output.items.is_none() {
-        bail!("no items were returned from dynamo query")
-    }
-    
-    let items = output.items();
-    
-    // println!("query returned {} items", items.len());
+        .return_consumed_capacity(ReturnConsumedCapacity::Total)
+        .into_paginator()
+        .send();
     
-    for (index, item) in items.iter().enumerate() {
-        stats.push(match from_item(item.clone()) {
-            Ok(a) => a,
-            Err(e) => {
-                let json: Value = from_item(item.clone()).unwrap();
-                bail!("failed to deserialize: '{e}' \n {}", serde_json::to_string(&json).unwrap())
-            },
-        });
+    while let Some(page) = pages.next().await {
+        let page = page.wrap_err_with(||format!("failed to get all stats|{table_name}|{bucket:?}"))?;
+        let items = page.items();
+        
+        for item in items {
+            stats.push(match from_item(item.clone()) {
+                Ok(a) => a,
+                Err(e) => {
+                    let json: Value = from_item(item.clone()).unwrap();
+                    bail!("failed to deserialize: '{e}' \n {}", serde_json::to_string(&json).unwrap())
+                },
+            });
+        }
+        summary.add_page(items.len(), page.consumed_capacity());
+        on_progress(&summary);
     }
     
-    Ok(stats)
+    Ok((stats, summary))
 }
 
-pub async fn get_all_bot_details(client: &Client, table_name: &str)-> color_eyre::Result<Vec<BotSettings>> {
+/// Scans the cron table for every bot's settings.
+/// `on_progress` is called after each page
+pub async fn get_all_bot_details(client: &Client, table_name: &str, mut on_progress: impl FnMut(&QuerySummary))-> color_eyre::Result<(Vec<BotSettings>, QuerySummary)> {
     let page_size = 100;
     let mut bots: Vec<BotSettings> = vec![];
+    let mut summary = QuerySummary::default();
     
-    let items: Result<Vec<_>, _>  = client
+    let mut pages = client
         .scan()
         .table_name(table_name)
         .limit(page_size)
+        .return_consumed_capacity(ReturnConsumedCapacity::Total)
         .into_paginator()
-        .items()
-        .send()
-        .collect()
-        .await;
-    let raw_items = items.wrap_err("failed getting bot settings")?;
-    for item in &raw_items {
-        bots.push(match from_item(item.clone()) {
-            Ok(a) => a,
-            Err(e) => {
-                // let json: Value = from_item(item.clone()).unwrap();
-                bail!("failed to deserialize: '{e}' \n {:?}", item);
-            }
-        })
+        .send();
+    
+    while let Some(page) = pages.next().await {
+        let page = page.wrap_err("failed getting bot settings")?;
+        for item in page.items() {
+            bots.push(match from_item(item.clone()) {
+                Ok(a) => a,
+                Err(e) => {
+                    // let json: Value = from_item(item.clone()).unwrap();
+                    bail!("failed to deserialize: '{e}' \n {:?}", item);
+                }
+            })
+        }
+        summary.add_page(page.items().len(), page.consumed_capacity());
+        on_progress(&summary);
     }
     
-    Ok(bots)
+    Ok((bots, summary))
 }
 
+/// Scans every queue's record from the event table
+pub async fn get_all_queue_records(client: &Client, table_name: &str, mut on_progress: impl FnMut(&QuerySummary)) -> color_eyre::Result<(Vec<QueueRecord>, QuerySummary)> {
+    let mut queues: Vec<QueueRecord> = vec![];
+    let mut summary = QuerySummary::default();
 
-// Queries the dynamo table for all stats in range for a given bot_id (KINDA WORKS????)
-pub async fn get_bot_stats_from_time(client: &Client, bot_id: &str ,table_name: &str, bucket: BotBucket) -> color_eyre::Result<Vec<BotDynamoStatsRecord>> {
-    
-    
-    println!("bucket = {bucket}");
-    println!("bot_id = {bot_id}");
+    let mut pages = client
+        .scan()
+        .table_name(table_name)
+        .limit(100)
+        .return_consumed_capacity(ReturnConsumedCapacity::Total)
+        .into_paginator()
+        .send();
+
+    while let Some(page) = pages.next().await {
+        let page = page.wrap_err("failed getting queue records")?;
+        // One malformed queue shouldn't hide every other queue on the bus
+        queues.extend(page.items().iter().filter_map(|item| from_item(item.clone()).ok()));
+        summary.add_page(page.items().len(), page.consumed_capacity());
+        on_progress(&summary);
+    }
+
+    Ok((queues, summary))
+}
+
+/// Scans every entry in the system or settings table
+pub async fn get_table_entries(client: &Client, table_name: &str) -> color_eyre::Result<(Vec<TableEntry>, QuerySummary)> {
+    let mut entries: Vec<TableEntry> = vec![];
+    let mut summary = QuerySummary::default();
+
+    let mut pages = client
+        .scan()
+        .table_name(table_name)
+        .limit(100)
+        .return_consumed_capacity(ReturnConsumedCapacity::Total)
+        .into_paginator()
+        .send();
+
+    while let Some(page) = pages.next().await {
+        let page = page.wrap_err_with(|| format!("failed scanning {table_name}"))?;
+        for item in page.items() {
+            entries.push(from_item(item.clone()).wrap_err_with(|| format!("failed to deserialize {table_name} entry {item:?}"))?);
+        }
+        summary.add_page(page.items().len(), page.consumed_capacity());
+    }
+
+    Ok((entries, summary))
+}
+
+/// Pauses, resumes, triggers or moves a read checkpoint of a bot on the cron table. The update is conditional on
+/// the field still holding the value it was loaded with, so nothing is written when someone else changed it since
+pub async fn update_bot_action(client: &Client, table_name: &str, pending: &PendingAction, now: i64) -> color_eyre::Result<()> {
+    let bot = &pending.bot;
+    let (names, value, loaded) = match (pending.action, pending.checkpoint.as_ref()) {
+        (BotAction::Pause, _) => (vec![("#paused", "paused")], AttributeValue::Bool(true), bot.paused.map(AttributeValue::Bool)),
+        (BotAction::Resume, _) => (vec![("#paused", "paused")], AttributeValue::Bool(false), bot.paused.map(AttributeValue::Bool)),
+        (BotAction::Trigger, _) => (vec![("#trigger", "trigger")], AttributeValue::N(now.to_string()), bot.trigger.map(|a| AttributeValue::N(a.to_string()))),
+        (BotAction::Checkpoint, Some(change)) => (
+            vec![("#checkpoints", "checkpoints"), ("#read", "read"), ("#queue", change.queue.as_str()), ("#checkpoint", "checkpoint")],
+            AttributeValue::S(change.after.clone()),
+            change.before.clone().map(AttributeValue::S),
+        ),
+        (BotAction::Checkpoint, None) => bail!("no checkpoint change given for {}", bot.id),
+    };
+    let path = names.iter().map(|a| a.0).collect::<Vec<_>>().join(".");
+
+    let mut request = client.update_item()
+        .table_name(table_name)
+        .key("id", AttributeValue::S(bot.id.clone()))
+        .update_expression(format!("SET {path} = :value"))
+        .expression_attribute_names("#id", "id")
+        .expression_attribute_values(":value", value);
+    for (placeholder, name) in names {
+        request = request.expression_attribute_names(placeholder, name);
+    }
+    let request = match loaded {
+        Some(loaded) => request
+            .condition_expression(format!("attribute_exists(#id) AND {path} = :loaded"))
+            .expression_attribute_values(":loaded", loaded),
+        None => request.condition_expression(format!("attribute_exists(#id) AND attribute_not_exists({path})")),
+    };
+
+    match request.send().await {
+        Ok(_) => Ok(()),
+        Err(e) if e.as_service_error().is_some_and(|a| a.is_conditional_check_failed_exception()) => {
+            bail!("{} was changed or removed since it was loaded, wait for a refresh and try again", bot.id)
+        },
+        Err(e) => Err(e).wrap_err_with(|| format!("failed to {} {}|{table_name}", pending.action, bot.id)),
+    }
+}
+
+/// Queries a single bot's stats for the bucket's period and time range on the table's `id`/`bucket` key,
+/// following `LastEvaluatedKey` until every page has been read. `on_progress` is called after each page
+pub async fn get_bot_stats_from_time(client: &Client, table_name: &str, bot_id: &str, bucket: AllBuckets, mut on_progress: impl FnMut(&QuerySummary)) -> color_eyre::Result<(Vec<BotDynamoStatsRecord>, QuerySummary)> {
+    let (Some(start), Some(end)) = (DateTime::from_timestamp_millis(bucket.start), DateTime::from_timestamp_millis(bucket.end)) else {
+        bail!("bucket range is out of range|{bucket:?}");
+    };
+    let mut stats: Vec<BotDynamoStatsRecord> = vec![];
+    let mut summary = QuerySummary::default();
     
-    let mut stats = vec![];
-    let output = client.query()
+    // Buckets are `{period}_{date}` with zero padded dates so they sort in time order within a period
+    let mut pages = client.query()
         .table_name(table_name)
-        // .index_name("period-time-index")
+        .key_condition_expression("#id = :id and #bucket between :start and :end")
         .expression_attribute_names("#id", "id")
         .expression_attribute_names("#bucket", "bucket")
         .expression_attribute_values(":id", AttributeValue::S(bot_id.to_owned()))
-        .expression_attribute_values(":bucket", AttributeValue::S(bucket.to_string()))
-        .key_condition_expression("#id = :id and #bucket > :bucket")
-        .send().await.wrap_err("failed to get bot stats")?;
-    
-    // let output = client.query()
-    //     .table_name(table_name)
-    //     .index_name("period-time-index")
-    //     .expression_attribute_names("#time", "time")
-    //     .expression_attribute_names("#period", "period")
-    //     .expression_attribute_values(":start", )
-    
-    if output.items.is_none() {
-        bail!("no items were returned from dynamo query")
-    }
-    let items = output.items();
-    
-    println!("query returned {} items", items.len());
-   
-    for (index, item) in items.iter().enumerate() {
-        stats.push(match from_item(item.clone()) {
-            Ok(a) => a,
-            Err(e) => {
-                let json: Value = from_item(item.clone()).unwrap();
-                bail!("failed to deserialize: '{e}' \n {}", serde_json::to_string(&json).unwrap())
-            },
-        });
+        .expression_attribute_values(":start", AttributeValue::S(BotBucket::new(bucket.period, start).to_string()))
+        .expression_attribute_values(":end", AttributeValue::S(BotBucket::new(bucket.period, end).to_string()))
+        .return_consumed_capacity(ReturnConsumedCapacity::Total)
+        .into_paginator()
+        .send();
+    
+    while let Some(page) = pages.next().await {
+        let page = page.wrap_err_with(|| format!("failed to get bot stats|{table_name}|{bot_id}|{bucket:?}"))?;
+        let items = page.items();
+        
+        for item in items {
+            stats.push(match from_item(item.clone()) {
+                Ok(a) => a,
+                Err(e) => {
+                    let json: Value = from_item(item.clone()).unwrap();
+                    bail!("failed to deserialize: '{e}' \n {}", serde_json::to_string(&json).unwrap())
+                },
+            });
+        }
+        summary.add_page(items.len(), page.consumed_capacity());
+        on_progress(&summary);
+    }
+    
+    Ok((stats, summary))
+}
+
+/// Queries a page of events on a queue from the stream table's `event`/`eid` key, unpacking batched items.
+/// Pages are read until `limit` events are found, and come back oldest first whichever way they were queried
+pub async fn get_stream_events(client: &Client, table_name: &str, queue: &str, position: &StreamPosition, limit: usize) -> color_eyre::Result<(Vec<StreamEvent>, QuerySummary)> {
+    let (condition, eid) = match position {
+        StreamPosition::Start | StreamPosition::End => ("#event = :event", None),
+        StreamPosition::At(eid) => ("#event = :event and #eid >= :eid", Some(eid)),
+        StreamPosition::After(eid) => ("#event = :event and #eid > :eid", Some(eid)),
+        StreamPosition::Before(eid) => ("#event = :event and #eid < :eid", Some(eid)),
+    };
+    let forward = !matches!(position, StreamPosition::End | StreamPosition::Before(_));
+    let mut events: Vec<StreamEvent> = vec![];
+    let mut summary = QuerySummary::default();
+    
+    let mut request = client.query()
+        .table_name(table_name)
+        .key_condition_expression(condition)
+        .expression_attribute_names("#event", "event")
+        .expression_attribute_values(":event", AttributeValue::S(queue_name(queue)))
+        .scan_index_forward(forward)
+        .limit(limit as i32)
+        .return_consumed_capacity(ReturnConsumedCapacity::Total);
+    if let Some(eid) = eid {
+        request = request
+            .expression_attribute_names("#eid", "eid")
+            .expression_attribute_values(":eid", AttributeValue::S(eid.clone()));
     }
+    let mut pages = request.into_paginator().send();
     
+    while let Some(page) = pages.next().await {
+        let page = page.wrap_err_with(|| format!("failed to get events|{table_name}|{queue}|{position}"))?;
+        let items = page.items();
+        
+        for item in items {
+            let event: StreamEvent = from_item(item.clone())
+                .wrap_err_with(|| format!("failed to deserialize event|{:?}", item.get("eid")))?;
+            let mut unpacked = event.unpack()?;
+            if !forward {
+                unpacked.reverse();
+            }
+            events.extend(unpacked);
+        }
+        summary.add_page(items.len(), page.consumed_capacity());
+        if events.len() >= limit {
+            break;
+        }
+    }
     
-    Ok(stats)
-}
\ No newline at end of file
+    if !forward {
+        events.reverse();
+    }
+    Ok((events, summary))
+}
+
+#[cfg(test)]
+mod dynamo_tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{BotBucket, Period, StatsRange};
+
+    #[test]
+    fn buckets_are_formatted_for_every_period() {
+        // A wednesday
+        let date = Utc.with_ymd_and_hms(2024, 7, 17, 9, 38, 12).unwrap();
+        let bucket = |period| BotBucket::new(period, date).to_string();
+
+        assert_eq!(bucket(Period::Minute), "minute_2024-07-17 09:38");
+        assert_eq!(bucket(Period::Minute5), "minute_5_2024-07-17 09:35");
+        assert_eq!(bucket(Period::Minute15), "minute_15_2024-07-17 09:30");
+        assert_eq!(bucket(Period::Hour), "hour_2024-07-17 09");
+        assert_eq!(bucket(Period::Day), "day_2024-07-17");
+        assert_eq!(bucket(Period::Week), "week_2024-07-14");
+    }
+
+    #[test]
+    fn ranges_cycle_in_both_directions() {
+        let last = StatsRange::PRESETS[4];
+
+        assert_eq!(StatsRange::LOAD.cycle(false), StatsRange::BOT_HISTORY);
+        assert_eq!(StatsRange::BOT_HISTORY.cycle(true), StatsRange::LOAD);
+        assert_eq!(last.cycle(false), StatsRange::PRESETS[0]);
+        assert_eq!(StatsRange::PRESETS[0].cycle(true), last);
+        assert_eq!(StatsRange::BOT_HISTORY.to_string(), "7d @ hour");
+        assert_eq!(StatsRange::PRESETS[1].to_string(), "6h @ minute_5");
+    }
+}
@@ -1,8 +1,8 @@
 use std::panic;
 
-use color_eyre::{config::HookBuilder, eyre};
+use color_eyre::config::HookBuilder;
 
-use crate::{restore, Tui};
+use crate::restore;
 
 
 // #[derive(Debug, Error)]
@@ -25,14 +25,9 @@ pub fn install_hooks() -> color_eyre::Result<()> {
         panic_hook(panic_info);
     }));
     
-    // convert from a color_eyre Eyrehook to a eyre ErrorHook
-    let eyre_hook = eyre_hook.into_eyre_hook();
-    eyre::set_hook(Box::new(
-        move |error: &(dyn std::error::Error + 'static)| {
-            restore().unwrap();
-            eyre_hook(error)
-        }
-    ))?;
+    // Errors are returned up to main, which restores the terminal before printing them.
+    // Restoring here would drop out of raw mode for errors the app handles, like a failed refresh
+    eyre_hook.install()?;
     
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,61 @@
+use chrono::DateTime;
+
+/// A parsed leo event id, e.g. `z/2024/07/15/12/30/1721046600000-0000001`.
+/// The date path is only there for s3 partitioning, the millis and sequence are what order events
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EventId {
+    pub timestamp: i64,
+    pub sequence: u32,
+}
+
+impl EventId {
+    pub fn parse(eid: &str) -> Option<Self> {
+        let tail = eid.strip_prefix("z/")?.rsplit('/').next()?;
+        let (timestamp, sequence) = match tail.split_once('-') {
+            Some((timestamp, sequence)) => (timestamp, sequence.parse().ok()?),
+            None => (tail, 0),
+        };
+
+        Some(Self {
+            timestamp: timestamp.parse().ok()?,
+            sequence,
+        })
+    }
+
+    /// The checkpoint that starts reading at `timestamp` millis. With no sequence it sorts before every event written then
+    pub fn before_timestamp(timestamp: i64) -> Option<String> {
+        let date = DateTime::from_timestamp_millis(timestamp)?;
+        Some(format!("z/{}/{timestamp}", date.format("%Y/%m/%d/%H/%M")))
+    }
+
+    /// Builds the event id the leo sdk would give the `sequence`th event written at `timestamp` millis
+    pub fn from_timestamp(timestamp: i64, sequence: u32) -> Option<String> {
+        let date = DateTime::from_timestamp_millis(timestamp)?;
+        Some(format!("z/{}/{timestamp}-{sequence:07}", date.format("%Y/%m/%d/%H/%M")))
+    }
+}
+
+#[cfg(test)]
+mod event_id_tests {
+    use super::EventId;
+
+    #[test]
+    fn parse_works() {
+        assert_eq!(
+            EventId::parse("z/2024/07/15/12/30/1721046600000-0000001"),
+            Some(EventId { timestamp: 1721046600000, sequence: 1 })
+        );
+        assert_eq!(
+            EventId::parse("z/2024/07/15/12/30/1721046600000"),
+            Some(EventId { timestamp: 1721046600000, sequence: 0 })
+        );
+        assert_eq!(EventId::parse("not an eid"), None);
+    }
+
+    #[test]
+    fn from_timestamp_round_trips() {
+        let eid = EventId::from_timestamp(1721046600000, 12).unwrap();
+        assert_eq!(eid, "z/2024/07/15/12/30/1721046600000-0000012");
+        assert_eq!(EventId::parse(&eid), Some(EventId { timestamp: 1721046600000, sequence: 12 }));
+    }
+}
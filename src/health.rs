@@ -0,0 +1,232 @@
+use std::{collections::HashMap, fmt::Display};
+
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::{bot_stats::{merge_bot_stats, BotDynamoStatsRecord}, lag::{worst_lags, ReadLag}, pages::{bot::BotSettings, queue::QueueRecord}};
+
+/// How a bot is doing, ordered from least to most in need of attention
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Healthy,
+    Idle,
+    Paused,
+    Warning,
+    Critical,
+}
+
+impl Display for HealthStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            HealthStatus::Healthy => "HEALTHY",
+            HealthStatus::Idle => "IDLE",
+            HealthStatus::Paused => "PAUSED",
+            HealthStatus::Warning => "WARNING",
+            HealthStatus::Critical => "CRITICAL",
+        };
+        f.pad(label)
+    }
+}
+
+/// A bot's status and why it was given it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Health {
+    pub status: HealthStatus,
+    pub reason: String,
+}
+
+/// The limits a bot is held to, set per bus under `Health` in the leo config
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all="PascalCase", default)]
+pub struct HealthThresholds {
+    /// Errors in a row recorded on the cron table
+    pub error_count_warning: u32,
+    pub error_count_critical: u32,
+    /// Errors over executions in the loaded stats
+    pub error_rate_warning: f64,
+    pub error_rate_critical: f64,
+    /// Minutes a read can be behind the head of its queue
+    pub lag_warning_minutes: i64,
+    pub lag_critical_minutes: i64,
+    /// How far the last hour's units can fall below the hourly average, 0.5 being half
+    pub throughput_drop_warning: f64,
+    /// Minutes past its scheduled trigger before a bot is overdue
+    pub trigger_overdue_minutes: i64,
+    /// Minutes without an invoke before a bot with no executions is idle
+    pub idle_minutes: i64,
+}
+
+impl Default for HealthThresholds {
+    fn default() -> Self {
+        Self {
+            error_count_warning: 1,
+            error_count_critical: 5,
+            error_rate_warning: 0.05,
+            error_rate_critical: 0.25,
+            lag_warning_minutes: 15,
+            lag_critical_minutes: 60,
+            throughput_drop_warning: 0.5,
+            trigger_overdue_minutes: 15,
+            idle_minutes: 60,
+        }
+    }
+}
+
+impl Health {
+    /// Scores a bot from its settings, its own stats in the loaded window and its worst read lag
+    pub fn assess(bot: &BotSettings, stats: &[BotDynamoStatsRecord], lag: Option<&ReadLag>, thresholds: &HealthThresholds, now: i64) -> Self {
+        if bot.paused.unwrap_or_default() {
+            return Self { status: HealthStatus::Paused, reason: "paused".to_string() };
+        }
+
+        let mut findings: Vec<(HealthStatus, String)> = vec![];
+        let mut flag = |hit: bool, critical: bool, reason: String| {
+            if hit {
+                findings.push((if critical { HealthStatus::Critical } else { HealthStatus::Warning }, reason));
+            }
+        };
+
+        let error_count = bot.error_count.unwrap_or_default();
+        flag(
+            error_count >= thresholds.error_count_warning.max(1),
+            error_count >= thresholds.error_count_critical,
+            format!("{error_count} errors in a row"),
+        );
+
+        let last_run = bot.instances.iter()
+            .flat_map(|a| a.values())
+            .max_by_key(|a| a.invoke_time.or(a.start_time));
+        let failed = last_run.is_some_and(|a| a.status.as_deref() == Some("error") || a.result.as_deref() == Some("error"));
+        flag(failed && error_count == 0, false, "last run failed".to_string());
+
+        let merged = merge_bot_stats(stats).execution_stats;
+        if merged.completions > 0 {
+            let rate = merged.errors as f64 / merged.completions as f64;
+            flag(
+                rate >= thresholds.error_rate_warning,
+                rate >= thresholds.error_rate_critical,
+                format!("{:.0}% of runs errored", rate * 100.0),
+            );
+        }
+
+        if let Some(lag) = lag {
+            let minutes = lag.behind() / Duration::minutes(1).num_milliseconds();
+            flag(
+                minutes >= thresholds.lag_warning_minutes,
+                minutes >= thresholds.lag_critical_minutes,
+                format!("reads {minutes}m behind"),
+            );
+        }
+
+        if let Some(drop) = throughput_drop(stats, now) {
+            flag(drop >= thresholds.throughput_drop_warning, false, format!("throughput down {:.0}%", drop * 100.0));
+        }
+
+        if let Some(trigger) = bot.scheduled_trigger {
+            let overdue = (now - trigger) / Duration::minutes(1).num_milliseconds();
+            flag(overdue >= thresholds.trigger_overdue_minutes, false, format!("trigger {overdue}m overdue"));
+        }
+
+        if let Some(worst) = findings.iter().map(|a| a.0).max() {
+            let reason = findings.iter()
+                .filter(|a| a.0 == worst)
+                .map(|a| a.1.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Self { status: worst, reason };
+        }
+
+        let idle_for = bot.invoke_time.map(|a| (now - a) / Duration::minutes(1).num_milliseconds());
+        if merged.completions == 0 && idle_for.is_none_or(|a| a >= thresholds.idle_minutes) {
+            let reason = match idle_for {
+                Some(minutes) => format!("no runs, last invoked {minutes}m ago"),
+                None => "never invoked".to_string(),
+            };
+            return Self { status: HealthStatus::Idle, reason };
+        }
+
+        Self {
+            status: HealthStatus::Healthy,
+            reason: format!("{} runs, {} errors", merged.completions, merged.errors),
+        }
+    }
+}
+
+/// How far units in the last full hour fell below the hourly average of the hours before it.
+/// `None` without at least two earlier hours to compare against
+fn throughput_drop(stats: &[BotDynamoStatsRecord], now: i64) -> Option<f64> {
+    let hour = Duration::hours(1).num_milliseconds();
+    // The hour in progress is still filling up so the comparison stops where it starts
+    let end = now - now % hour;
+    let start = stats.iter().map(|a| a.time).min()?;
+    let earlier_hours = (end - hour - start) / hour;
+    if earlier_hours < 2 {
+        return None;
+    }
+
+    let units = |from: i64, to: i64| -> u64 {
+        stats.iter()
+            .filter(|a| (from..to).contains(&a.time))
+            .filter_map(|a| a.current.execution.as_ref()?.units)
+            .map(u64::from)
+            .sum()
+    };
+    let average = units(end - hour * (earlier_hours + 1), end - hour) as f64 / earlier_hours as f64;
+    if average == 0.0 {
+        return None;
+    }
+
+    Some((1.0 - units(end - hour, end) as f64 / average).max(0.0))
+}
+
+/// Scores every bot, keyed by bot id
+pub fn assess_all(bots: &[BotSettings], stats: &[BotDynamoStatsRecord], records: &[QueueRecord], thresholds: &HealthThresholds, now: i64) -> HashMap<String, Health> {
+    let mut stats_by_bot: HashMap<&str, Vec<BotDynamoStatsRecord>> = HashMap::new();
+    for record in stats {
+        stats_by_bot.entry(record.id.as_str()).or_default().push(record.clone());
+    }
+    let lags = worst_lags(bots, stats, records, now);
+
+    bots.iter()
+        .map(|bot| {
+            let bot_stats = stats_by_bot.get(bot.id.as_str()).map(Vec::as_slice).unwrap_or_default();
+            (bot.id.clone(), Health::assess(bot, bot_stats, lags.get(&bot.id), thresholds, now))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod health_tests {
+    use crate::{leo_config::LeoConfig, source::fixtures::{self, NOW}};
+
+    use super::{assess_all, HealthStatus, HealthThresholds};
+
+    #[test]
+    fn fixture_bots_are_classified() {
+        let (bots, stats) = (fixtures::bots(), fixtures::minute_stats());
+
+        let health = assess_all(&bots, &stats, &[], &HealthThresholds::default(), NOW);
+
+        assert_eq!(health["bot:customer_loader"].status, HealthStatus::Paused);
+        assert_eq!(health["bot:legacy_customer_sync"].status, HealthStatus::Critical);
+        assert!(health["bot:legacy_customer_sync"].reason.contains("6 errors in a row"));
+        assert_eq!(health["bot:order_loader"].status, HealthStatus::Healthy);
+
+        let lenient = HealthThresholds { error_count_critical: 10, error_rate_critical: 1.0, lag_critical_minutes: 1000, ..HealthThresholds::default() };
+        let health = assess_all(&bots, &stats, &[], &lenient, NOW);
+        assert_eq!(health["bot:legacy_customer_sync"].status, HealthStatus::Warning);
+    }
+
+    #[test]
+    fn thresholds_default_when_missing_from_the_config() {
+        let config: LeoConfig = serde_json::from_str(r#"{
+            "LeoCron": "c", "LeoEvent": "e", "LeoFirehoseStream": "f", "LeoKinesisStream": "k", "LeoS3": "s",
+            "LeoSettings": "s", "LeoStats": "s", "LeoStream": "s", "LeoSystem": "s", "Region": "us-east-1",
+            "Health": {"LagWarningMinutes": 5}
+        }"#).unwrap();
+
+        assert_eq!(config.health.lag_warning_minutes, 5);
+        assert_eq!(config.health.error_count_critical, HealthThresholds::default().error_count_critical);
+    }
+}
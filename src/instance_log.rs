@@ -0,0 +1,218 @@
+use std::{fmt, io::Read};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use color_eyre::eyre::Context;
+use flate2::read::GzDecoder;
+use serde::{de::{self, IgnoredAny, MapAccess, SeqAccess, Visitor}, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+/// The log a bot instance kept from its last run, gzipped by the leo sdk.
+/// It's binary on the cron table and base64 in json dumps, and is only unzipped when it's looked at
+#[derive(Debug, Clone, PartialEq)]
+pub struct GzippedLog(pub Vec<u8>);
+
+/// How much of a log is unzipped, past this the rest is cut off so a runaway log can't stall the ui
+pub const MAX_LOG_BYTES: u64 = 8 * 1024 * 1024;
+
+impl GzippedLog {
+    /// Unzips the log into lines. The sdk stores either plain text or json, with a line for each entry in a json array.
+    /// Only the first [`MAX_LOG_BYTES`] are read, with a last line saying so when the log is longer
+    pub fn lines(&self) -> color_eyre::Result<Vec<String>> {
+        let mut raw = vec![];
+        GzDecoder::new(self.0.as_slice())
+            .take(MAX_LOG_BYTES + 1)
+            .read_to_end(&mut raw)
+            .wrap_err("log is not valid gzip")?;
+        let truncated = raw.len() as u64 > MAX_LOG_BYTES;
+        raw.truncate(MAX_LOG_BYTES as usize);
+        let text = String::from_utf8_lossy(&raw);
+
+        let mut lines: Vec<String> = match serde_json::from_str::<Value>(&text) {
+            Ok(Value::String(text)) => text.lines().map(str::to_owned).collect(),
+            Ok(Value::Array(entries)) => entries.into_iter()
+                .flat_map(|a| match a {
+                    Value::String(line) => line.lines().map(str::to_owned).collect::<Vec<_>>(),
+                    a => vec![a.to_string()],
+                })
+                .collect(),
+            Ok(value @ Value::Object(_)) => serde_json::to_string_pretty(&value)?.lines().map(str::to_owned).collect(),
+            _ => text.lines().map(str::to_owned).collect(),
+        };
+        if truncated {
+            lines.push(format!("... the rest of the log is cut off, only the first {}MiB is shown", MAX_LOG_BYTES / 1024 / 1024));
+        }
+
+        Ok(lines)
+    }
+}
+
+impl Serialize for GzippedLog {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for GzippedLog {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(GzippedLogVisitor)
+    }
+}
+
+struct GzippedLogVisitor;
+
+impl<'de> Visitor<'de> for GzippedLogVisitor {
+    type Value = GzippedLog;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "gzipped bytes or a base64 string of them")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(GzippedLog(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(GzippedLog(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        STANDARD.decode(v).map(GzippedLog).map_err(E::custom)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = vec![];
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(GzippedLog(bytes))
+    }
+}
+
+/// Reads an instance's log as `None` when it's missing or isn't gzipped bytes, so an odd log never stops its bot from loading
+pub fn deserialize_lenient<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<GzippedLog>, D::Error> {
+    deserializer.deserialize_any(LenientLogVisitor)
+}
+
+/// Takes whatever the log is, consuming values it can't use instead of failing on them
+struct LenientLogVisitor;
+
+impl<'de> Visitor<'de> for LenientLogVisitor {
+    type Value = Option<GzippedLog>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "anything, with gzipped bytes or a base64 string of them kept as the log")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(Some(GzippedLog(v.to_vec())))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(Some(GzippedLog(v)))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(STANDARD.decode(v).ok().map(GzippedLog))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        // Every element is read even after one isn't a byte so the rest of the item still lines up
+        let mut bytes = Some(vec![]);
+        while let Some(element) = seq.next_element::<Value>()? {
+            match (bytes.as_mut(), element.as_u64().and_then(|a| u8::try_from(a).ok())) {
+                (Some(bytes), Some(byte)) => bytes.push(byte),
+                _ => bytes = None,
+            }
+        }
+        Ok(bytes.map(GzippedLog))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+        Ok(None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserialize_lenient(deserializer)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_i64<E: de::Error>(self, _: i64) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_u64<E: de::Error>(self, _: u64) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_f64<E: de::Error>(self, _: f64) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod instance_log_tests {
+    use std::io::Write;
+
+    use aws_sdk_dynamodb::{primitives::Blob, types::AttributeValue};
+    use flate2::{write::GzEncoder, Compression};
+
+    use crate::{pages::bot::Instance, source::fixtures};
+
+    use super::{GzippedLog, MAX_LOG_BYTES};
+
+    fn gzip(text: &str) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn logs_decode_from_binary_and_base64() {
+        let bytes = gzip(r#"["starting", "Error: timed out\n    at handler"]"#);
+
+        let from_dynamo: GzippedLog = serde_dynamo::from_attribute_value(AttributeValue::B(Blob::new(bytes.clone()))).unwrap();
+        let round_trip: GzippedLog = serde_json::from_value(serde_json::to_value(&from_dynamo).unwrap()).unwrap();
+        assert_eq!(round_trip, GzippedLog(bytes));
+        assert_eq!(round_trip.lines().unwrap(), vec!["starting", "Error: timed out", "    at handler"]);
+
+        assert_eq!(GzippedLog(gzip("plain\ntext")).lines().unwrap(), vec!["plain", "text"]);
+        assert!(GzippedLog(b"not gzip".to_vec()).lines().is_err());
+    }
+
+    #[test]
+    fn oversized_logs_are_cut_off() {
+        let line = "x".repeat(1023);
+        let huge = gzip(&format!("{line}\n").repeat(MAX_LOG_BYTES as usize / 1024 + 10));
+
+        let lines = GzippedLog(huge).lines().unwrap();
+        assert_eq!(lines.len(), MAX_LOG_BYTES as usize / 1024 + 1);
+        assert!(lines.last().unwrap().contains("cut off"));
+    }
+
+    #[test]
+    fn unreadable_logs_are_dropped_without_failing_the_bot() {
+        let bots = fixtures::bots();
+        let archiver = bots.iter().find(|a| a.id == "bot:invoice_archiver").unwrap();
+        assert_eq!(archiver.instances.as_ref().unwrap()["0"].log, None);
+
+        for log in [r#""not base64!""#, "42", r#"{"text": "hi"}"#, r#"["a", 1]"#, "null"] {
+            let instance: Instance = serde_json::from_str(&format!(r#"{{"log": {log}, "status": "complete"}}"#)).unwrap();
+            assert_eq!(instance.log, None, "{log}");
+            assert_eq!(instance.status.as_deref(), Some("complete"));
+        }
+        let instance: Instance = serde_json::from_str(r#"{"log": [31, 139]}"#).unwrap();
+        assert_eq!(instance.log, Some(GzippedLog(vec![31, 139])));
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{bot_stats::{BotDynamoStatsRecord, StatsOrEmpty}, event_id::EventId, pages::{bot::BotSettings, queue::{queue_name, QueueRecord}}};
+
+/// How far a bot's reads are behind a queue
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ReadLag {
+    /// The last event id the bot read
+    pub checkpoint: Option<String>,
+    /// When the last event the bot read was originally written, epoch millis
+    pub source_timestamp: Option<i64>,
+    /// The newest event id written to the queue
+    pub head: Option<String>,
+    /// Now minus `source_timestamp`, in millis
+    pub time_behind: Option<i64>,
+    /// How much older the bot's checkpoint is than the queue's head, in millis
+    pub event_lag: Option<i64>,
+    /// The bot has read everything written to the queue
+    pub caught_up: bool,
+}
+
+impl ReadLag {
+    pub fn new(checkpoint: Option<String>, source_timestamp: Option<i64>, head: Option<String>, now: i64) -> Self {
+        let read = checkpoint.as_deref().and_then(EventId::parse);
+        let written = head.as_deref().and_then(EventId::parse);
+
+        Self {
+            time_behind: source_timestamp.map(|a| (now - a).max(0)),
+            event_lag: read.zip(written).map(|(read, written)| (written.timestamp - read.timestamp).max(0)),
+            caught_up: read.zip(written).is_some_and(|(read, written)| read >= written),
+            checkpoint,
+            source_timestamp,
+            head,
+        }
+    }
+
+    /// How far the checkpoint is behind the head in millis, 0 once caught up
+    pub fn behind(&self) -> i64 {
+        if self.caught_up { 0 } else { self.event_lag.unwrap_or_default() }
+    }
+}
+
+/// The newest event id written to each queue by name, from the write checkpoints in the stats and every bot's settings
+/// along with the max event id in each queue's record, which covers queues nothing wrote to in the loaded stats
+pub fn queue_heads(bots: &[BotSettings], stats: &[BotDynamoStatsRecord], records: &[QueueRecord]) -> HashMap<String, String> {
+    let from_stats = stats.iter()
+        .filter_map(|a| match &a.current.write {
+            StatsOrEmpty::NotEmpty(queue_stats) => Some(queue_stats),
+            StatsOrEmpty::Empty {} => None,
+        })
+        .flatten()
+        .filter_map(|(queue, stat)| Some((queue_name(queue), stat.checkpoint.clone()?)));
+
+    let from_settings = bots.iter()
+        .filter_map(|a| a.checkpoints.as_ref()?.write.as_ref())
+        .flatten()
+        .filter_map(|(queue, detail)| Some((queue_name(queue), detail.checkpoint.as_ref()?.to_string())));
+
+    let from_records = records.iter()
+        .filter_map(|a| Some((queue_name(&a.event), a.max_eid.clone()?)));
+
+    let mut heads: HashMap<String, String> = HashMap::new();
+    for (queue, checkpoint) in from_stats.chain(from_settings).chain(from_records) {
+        let Some(event_id) = EventId::parse(&checkpoint) else {
+            continue;
+        };
+        let newer = heads.get(&queue)
+            .and_then(|a| EventId::parse(a))
+            .is_none_or(|head| event_id > head);
+        if newer {
+            heads.insert(queue, checkpoint);
+        }
+    }
+
+    heads
+}
+
+/// How far behind each bot is on each queue it reads, keyed by bot id and queue name.
+/// The read checkpoints in the bot settings are the most current so they win over the stats
+pub fn read_lags(bots: &[BotSettings], stats: &[BotDynamoStatsRecord], records: &[QueueRecord], now: i64) -> HashMap<(String, String), ReadLag> {
+    let heads = queue_heads(bots, stats, records);
+    let mut reads: HashMap<(String, String), (Option<String>, Option<i64>)> = HashMap::new();
+
+    for record in stats.iter().sorted_by_key(|a| a.time) {
+        if let StatsOrEmpty::NotEmpty(queue_stats) = &record.current.read {
+            for (queue, stat) in queue_stats {
+                let read = reads.entry((record.id.clone(), queue_name(queue))).or_default();
+                if stat.checkpoint.is_some() {
+                    read.0 = stat.checkpoint.clone();
+                }
+                read.1 = read.1.max(Some(stat.source_timestamp));
+            }
+        }
+    }
+
+    for bot in bots {
+        let Some(read_checkpoints) = bot.checkpoints.as_ref().and_then(|a| a.read.as_ref()) else {
+            continue;
+        };
+        for (queue, detail) in read_checkpoints {
+            reads.insert(
+                (bot.id.clone(), queue_name(queue)),
+                (detail.checkpoint.as_ref().map(|a| a.to_string()), detail.source_timestamp.as_ref().and_then(|a| a.as_i64())),
+            );
+        }
+    }
+
+    reads.into_iter()
+        .map(|((bot, queue), (checkpoint, source_timestamp))| {
+            let lag = ReadLag::new(checkpoint, source_timestamp, heads.get(&queue).cloned(), now);
+            ((bot, queue), lag)
+        })
+        .collect()
+}
+
+/// The read furthest behind its queue for each bot id
+pub fn worst_lags(bots: &[BotSettings], stats: &[BotDynamoStatsRecord], records: &[QueueRecord], now: i64) -> HashMap<String, ReadLag> {
+    let mut worst: HashMap<String, ReadLag> = HashMap::new();
+
+    for ((bot, _), lag) in read_lags(bots, stats, records, now) {
+        if worst.get(&bot).is_none_or(|a| lag.behind() > a.behind()) {
+            worst.insert(bot, lag);
+        }
+    }
+
+    worst
+}
+
+/// The queue a bot's read is furthest behind the head of, out of its reads with a checkpoint, keyed by queue name
+pub fn furthest_behind(lags: &HashMap<String, ReadLag>) -> Option<(&str, &ReadLag)> {
+    lags.iter()
+        .filter(|(_, lag)| lag.checkpoint.is_some())
+        // Ties go to the queue that sorts first so the same one is picked every time
+        .max_by(|a, b| a.1.behind().cmp(&b.1.behind()).then_with(|| b.0.cmp(a.0)))
+        .map(|(queue, lag)| (queue.as_str(), lag))
+}
+
+#[cfg(test)]
+mod lag_tests {
+    use crate::{bot_stats::BotDynamoStatsRecord, pages::{bot::BotSettings, queue::QueueRecord}};
+
+    use std::collections::HashMap;
+
+    use super::{furthest_behind, read_lags, ReadLag};
+
+    #[test]
+    fn lag_is_measured_against_the_newest_write() {
+        let lag = ReadLag::new(
+            Some(String::from("z/2024/07/15/09/58/1721037500000-0000003")),
+            Some(1721037470000),
+            Some(String::from("z/2024/07/15/11/59/1721044770000-0000010")),
+            1721044800000,
+        );
+
+        assert_eq!(lag.time_behind, Some(7330000));
+        assert_eq!(lag.event_lag, Some(7270000));
+        assert!(!lag.caught_up);
+
+        let caught_up = ReadLag::new(lag.head.clone(), None, lag.head.clone(), 1721044800000);
+        assert_eq!(caught_up.event_lag, Some(0));
+        assert!(caught_up.caught_up);
+    }
+
+    #[test]
+    fn settings_checkpoints_win_over_stats() {
+        let stats: Vec<BotDynamoStatsRecord> = serde_json::from_str(r#"[
+            {"id": "bot:writer", "bucket": "b", "period": "minute_15", "time": 2, "current": {"execution": null, "read": {},
+                "write": {"queue:a": {"checkpoint": "z/2024/07/15/11/59/1721044770000-0000010", "source_timestamp": 1721044740000, "timestamp": 1721044780000, "units": 5}}}},
+            {"id": "bot:reader", "bucket": "b", "period": "minute_15", "time": 1, "current": {"execution": null, "write": {},
+                "read": {"queue:a": {"checkpoint": "z/2024/07/15/10/00/1721037600000-0000000", "source_timestamp": 1721037600000, "timestamp": 1721037650000, "units": 5}}}}
+        ]"#).unwrap();
+        let bots: Vec<BotSettings> = serde_json::from_str(r#"[
+            {"id": "bot:reader", "checkpoints": {"read": {"queue:a": {"checkpoint": "z/2024/07/15/11/00/1721041200000-0000000", "source_timestamp": 1721041200000}}}}
+        ]"#).unwrap();
+
+        let lags = read_lags(&bots, &stats, &[], 1721044800000);
+        let lag = &lags[&(String::from("bot:reader"), String::from("a"))];
+
+        assert_eq!(lags.len(), 1);
+        assert_eq!(lag.source_timestamp, Some(1721041200000));
+        assert_eq!(lag.head.as_deref(), Some("z/2024/07/15/11/59/1721044770000-0000010"));
+        assert_eq!(lag.event_lag, Some(3570000));
+    }
+
+    #[test]
+    fn queue_records_give_heads_without_writes() {
+        let bots: Vec<BotSettings> = serde_json::from_str(r#"[
+            {"id": "bot:reader", "checkpoints": {"read": {"queue:idle": {"checkpoint": "z/2024/07/15/09/00/1721034000000-0000000", "source_timestamp": 1721034000000}}}}
+        ]"#).unwrap();
+        let records: Vec<QueueRecord> = serde_json::from_str(r#"[
+            {"event": "idle", "max_eid": "z/2024/07/15/10/00/1721037600000-0000002", "timestamp": 1721037600000}
+        ]"#).unwrap();
+
+        assert_eq!(read_lags(&bots, &[], &[], 1721044800000)[&(String::from("bot:reader"), String::from("idle"))].head, None);
+
+        let lags = read_lags(&bots, &[], &records, 1721044800000);
+        let lag = &lags[&(String::from("bot:reader"), String::from("idle"))];
+        assert_eq!(lag.head.as_deref(), Some("z/2024/07/15/10/00/1721037600000-0000002"));
+        assert_eq!(lag.event_lag, Some(3600000));
+    }
+
+    #[test]
+    fn furthest_behind_its_head_beats_oldest_checkpoint() {
+        let now = 1721044800000;
+        // An old checkpoint on a quiet queue it has caught up on, and a newer one well behind a busy queue
+        let lag = |checkpoint: Option<&str>, head: &str| ReadLag::new(checkpoint.map(String::from), None, Some(String::from(head)), now);
+        let lags = HashMap::from([
+            (String::from("quiet"), lag(Some("z/2024/07/15/08/00/1721030400000-0000000"), "z/2024/07/15/08/00/1721030400000-0000000")),
+            (String::from("busy"), lag(Some("z/2024/07/15/10/00/1721037600000-0000000"), "z/2024/07/15/11/59/1721044740000-0000000")),
+            (String::from("unread"), lag(None, "z/2024/07/15/11/59/1721044740000-0000000")),
+        ]);
+
+        let (queue, lag) = furthest_behind(&lags).unwrap();
+        assert_eq!(queue, "busy");
+        assert_eq!(lag.checkpoint.as_deref(), Some("z/2024/07/15/10/00/1721037600000-0000000"));
+        assert!(furthest_behind(&HashMap::new()).is_none());
+    }
+}
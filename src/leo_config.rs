@@ -1,7 +1,14 @@
-use std::{fs::read_to_string, path::Path};
+use std::{collections::HashMap, env::current_dir, fs::read_to_string};
 
+use aws_config::{sts::AssumeRoleProvider, BehaviorVersion, Region, SdkConfig};
+use aws_sdk_dynamodb::config::SharedCredentialsProvider;
+use color_eyre::eyre::Context;
 use serde::Deserialize;
 
+use crate::health::HealthThresholds;
+
+/// A bus in the leo config. Keys are PascalCase like the rest of the leo config (`LeoCron`, `Region`),
+/// the keys botmon adds (`Profile`, `RoleArn`, `EndpointUrl`, `Health` and `Production`) can also be snake_case
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all="PascalCase")]
 pub struct LeoConfig {
@@ -15,7 +22,73 @@ pub struct LeoConfig {
     pub leo_stream: String,
     pub leo_system: String,
     pub region: String,
+    /// aws profile to load credentials from, the default credential chain is used when missing
+    #[serde(alias = "profile")]
+    pub profile: Option<String>,
+    /// role to assume on top of the loaded credentials
+    #[serde(alias = "role_arn")]
+    pub role_arn: Option<String>,
+    /// overrides the dynamodb endpoint, for example to point at DynamoDB Local
+    #[serde(alias = "endpoint_url")]
+    pub endpoint_url: Option<String>,
+    /// limits used to score bot health on this bus
+    #[serde(default, alias = "health")]
+    pub health: HealthThresholds,
+    /// marks the bus as production, which is assumed when missing and the bus's name contains `prod`
+    #[serde(alias = "production")]
+    pub production: Option<bool>,
+}
+
+impl LeoConfig {
+    /// Whether `name`, this bus's key in the leo config, is a production bus
+    pub fn is_production(&self, name: &str) -> bool {
+        self.production.unwrap_or_else(|| name.to_lowercase().contains("prod"))
+    }
+    
+    /// Builds the aws config for this bus using its region and any profile, role or endpoint overrides
+    pub async fn sdk_config(&self) -> SdkConfig {
+        let mut loader = aws_config::defaults(BehaviorVersion::latest())
+            .region(Region::new(self.region.clone()));
+        if let Some(profile) = self.profile.as_ref() {
+            loader = loader.profile_name(profile);
+        }
+        if let Some(endpoint_url) = self.endpoint_url.as_ref() {
+            loader = loader.endpoint_url(endpoint_url);
+        }
+        let sdk_config = loader.load().await;
+        
+        match self.role_arn.as_ref() {
+            Some(role_arn) => {
+                let provider = AssumeRoleProvider::builder(role_arn)
+                    .session_name("botmon")
+                    .configure(&sdk_config)
+                    .build()
+                    .await;
+                sdk_config.into_builder()
+                    .credentials_provider(SharedCredentialsProvider::new(provider))
+                    .build()
+            },
+            None => sdk_config,
+        }
+    }
+}
+
+/// Reads every bus from the leo config file, defaulting to `./config.json`
+pub fn load_buses(config_path: Option<&str>) -> color_eyre::Result<HashMap<String, LeoConfig>> {
+    let leo_string = match config_path {
+        Some(path) => read_to_string(path)?,
+        None => read_to_string("./config.json").wrap_err_with(||current_dir().unwrap().to_str().unwrap().to_string())?,
+    };
+    let config: HashMap<String, LeoConfig> = serde_json::from_str(&leo_string)?;
+    
+    Ok(config)
+}
 
+/// Points every bus at the same dynamodb endpoint, used for `--endpoint-url`
+pub fn override_endpoint(buses: &mut HashMap<String, LeoConfig>, endpoint_url: Option<&str>) {
+    if let Some(endpoint_url) = endpoint_url {
+        buses.values_mut().for_each(|a| a.endpoint_url = Some(endpoint_url.to_owned()));
+    }
 }
 
 // impl LeoConfig {
@@ -25,3 +98,34 @@ pub struct LeoConfig {
 //     }
 // }
 
+#[cfg(test)]
+mod leo_config_tests {
+    use std::collections::HashMap;
+
+    use super::LeoConfig;
+
+    const TABLES: &str = r#""LeoCron": "c", "LeoEvent": "e", "LeoFirehoseStream": "f", "LeoKinesisStream": "k", "LeoS3": "s3",
+        "LeoSettings": "se", "LeoStats": "st", "LeoStream": "sm", "LeoSystem": "sy", "Region": "us-east-1""#;
+
+    #[test]
+    fn bus_overrides_read_in_either_case() {
+        let buses: HashMap<String, LeoConfig> = serde_json::from_str(&format!(r#"{{
+            "prod-pascal": {{{TABLES}, "Profile": "p", "RoleArn": "arn:r", "EndpointUrl": "http://localhost:8000",
+                "Health": {{"LagWarningMinutes": 5}}, "Production": false}},
+            "prod-snake": {{{TABLES}, "profile": "p", "role_arn": "arn:r", "endpoint_url": "http://localhost:8000",
+                "health": {{"LagWarningMinutes": 5}}, "production": false}},
+            "prod-bus": {{{TABLES}}}
+        }}"#)).unwrap();
+
+        for name in ["prod-pascal", "prod-snake"] {
+            let bus = &buses[name];
+            assert_eq!(bus.profile.as_deref(), Some("p"));
+            assert_eq!(bus.role_arn.as_deref(), Some("arn:r"));
+            assert_eq!(bus.endpoint_url.as_deref(), Some("http://localhost:8000"));
+            assert_eq!(bus.health.lag_warning_minutes, 5);
+            assert!(!bus.is_production(name));
+        }
+        assert!(buses["prod-bus"].is_production("prod-bus"));
+        assert_eq!(buses["prod-bus"].profile, None);
+    }
+}
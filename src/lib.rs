@@ -1,18 +1,30 @@
-use std::{io::{self, stdout, Stdout}, path::Path, str::FromStr};
+use std::{io::{self, stdout, Stdout}, str::FromStr};
 
 use argh::FromArgs;
 use chrono::Duration;
-use color_eyre::eyre::Error;
 use crossterm::{execute, terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen}};
 use ratatui::{backend::CrosstermBackend, style::{Color, Style}, Terminal};
 
 pub mod error;
 pub mod app;
+pub mod cli;
 pub mod bot_stats;
 pub mod dynamo;
 pub mod ui;
 pub mod pages;
 pub mod leo_config;
+pub mod loader;
+pub mod output;
+pub mod seed;
+pub mod event_id;
+pub mod source;
+pub mod lag;
+pub mod health;
+pub mod alerts;
+pub mod topology;
+pub mod actions;
+pub mod instance_log;
+pub mod stream;
 
 
 pub type Tui = Terminal<CrosstermBackend<Stdout>>;
@@ -21,7 +33,7 @@ pub type Tui = Terminal<CrosstermBackend<Stdout>>;
 pub fn init() -> io::Result<Tui> {
     execute!(stdout(), EnterAlternateScreen)?;
     enable_raw_mode()?;
-    Ok(Terminal::new(CrosstermBackend::new(stdout()))?)
+    Terminal::new(CrosstermBackend::new(stdout()))
 }
 
 /// Restores the terminal to it's previous state
@@ -35,9 +47,9 @@ pub fn restore() -> io::Result<()> {
 /// cli utility to get bot/queue stats and information
 pub struct AppParams {
     /// time for the app to refresh for new stats in seconds
-    /// the max amount of time is 10 minutes. The minimum is 10 seconds. If an invalid number is passed in 
-    /// the duration will be set to 10 seconds 
-    #[argh(option, short='r', from_str_fn(num_to_duration))]
+    /// the max amount of time is 10 minutes. The minimum is 10 seconds. If an invalid number is passed in
+    /// or it is left out the duration will be set to 10 seconds 
+    #[argh(option, short='r', from_str_fn(num_to_duration), default = "Duration::seconds(10)")]
     pub refresh_time: Duration,
     
     #[argh(option, short='c')]
@@ -50,6 +62,38 @@ pub struct AppParams {
     /// If not provided a select screen will display where a bus can be chosen.
     pub bus: Option<String>,
     
+    #[argh(option)]
+    /// overrides the dynamodb endpoint for every bus, for example http://localhost:8000 for DynamoDB Local
+    pub endpoint_url: Option<String>,
+    
+    #[argh(option)]
+    /// read bots and stats from the bot_settings.json and bot_stats.json dumps in this directory instead of a bus,
+    /// moving their times up to now. Handy for demos, e.g. --fixtures fixtures
+    pub fixtures: Option<String>,
+    
+    #[argh(option)]
+    /// path to the alert rules file. Defaults to alerts.json next to the leo config, which is skipped when it doesn't exist
+    pub alerts: Option<String>,
+    
+    #[argh(switch)]
+    /// allow pausing, resuming and triggering bots from the bot view. Every write is recorded to the audit log
+    pub allow_writes: bool,
+    
+    #[argh(switch)]
+    /// allow moving checkpoints on production buses, which are refused otherwise even with --allow-writes
+    pub allow_prod_checkpoints: bool,
+    
+    #[argh(option)]
+    /// path to the audit log of writes made to the bus. Defaults to botmon_audit.jsonl next to the leo config
+    pub audit_log: Option<String>,
+    
+    #[argh(option, short='f', default = "output::OutputFormat::Table")]
+    /// how headless commands print their results: json, csv or table. Defaults to table
+    pub format: output::OutputFormat,
+    
+    #[argh(subcommand)]
+    pub command: Option<cli::Command>,
+    
 }
 
 fn num_to_duration(value: &str) -> Result<Duration, String> {
@@ -82,7 +126,7 @@ pub enum Bus {
 impl FromStr for Bus {
     type Err = color_eyre::Report;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    fn from_str(_s: &str) -> Result<Self, Self::Err> {
         todo!()
     }
 }
@@ -0,0 +1,183 @@
+use std::sync::Arc;
+
+use tokio::{sync::mpsc::UnboundedSender, task::JoinHandle};
+
+use crate::{actions::{BotAction, CheckpointChange, PendingAction}, bot_stats::BotDynamoStatsRecord, dynamo::{QuerySummary, StatsRange}, pages::{bot::BotSettings, entries::{EntryTable, TableEntry}, queue::QueueRecord}, source::BotmonSource, stream::{StreamEvent, StreamPosition, PAGE_SIZE}};
+
+/// The tables read during a load
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoadTable {
+    Cron,
+    Stats,
+    Event,
+}
+
+/// Messages sent back to the app from a background load
+#[derive(Debug)]
+pub enum LoadMessage {
+    /// Running totals for the pages read from a table
+    Progress {
+        table: LoadTable,
+        summary: QuerySummary,
+    },
+    Loaded {
+        stats: Vec<BotDynamoStatsRecord>,
+        bots: Vec<BotSettings>,
+        queues: Vec<QueueRecord>,
+        progress: LoadProgress,
+    },
+    Failed(String),
+    /// The stats fetched for the bot being viewed over its range
+    BotHistory {
+        bot_id: String,
+        range: StatsRange,
+        stats: Result<Vec<BotDynamoStatsRecord>, String>,
+    },
+    /// An alert notification couldn't be sent
+    NotifyFailed(String),
+    /// A pause, resume or trigger finished writing to the cron table
+    ActionDone {
+        bot_id: String,
+        action: BotAction,
+        checkpoint: Option<CheckpointChange>,
+        result: Result<(), String>,
+    },
+    /// Every bot's stats fetched for a range other than the one loaded
+    RangeStats {
+        range: StatsRange,
+        stats: Result<Vec<BotDynamoStatsRecord>, String>,
+    },
+    /// A page of events read from the stream table
+    StreamEvents {
+        queue: String,
+        position: StreamPosition,
+        events: Result<Vec<StreamEvent>, String>,
+    },
+    /// The entries in the system or settings table
+    Entries {
+        table: EntryTable,
+        entries: Result<Vec<TableEntry>, String>,
+    },
+}
+
+/// How far along a load is and what it has cost so far
+#[derive(Debug, Default, Clone)]
+pub struct LoadProgress {
+    pub cron: QuerySummary,
+    pub stats: QuerySummary,
+    pub event: QuerySummary,
+    /// Why the load failed, or once loaded why a table it could do without didn't
+    pub error: Option<String>,
+}
+
+impl LoadProgress {
+    pub fn update(&mut self, table: LoadTable, summary: QuerySummary) {
+        match table {
+            LoadTable::Cron => self.cron = summary,
+            LoadTable::Stats => self.stats = summary,
+            LoadTable::Event => self.event = summary,
+        }
+    }
+    
+    pub fn pages(&self) -> usize {
+        self.cron.pages + self.stats.pages + self.event.pages
+    }
+    
+    pub fn items(&self) -> usize {
+        self.cron.items + self.stats.items + self.event.items
+    }
+    
+    pub fn consumed_capacity(&self) -> f64 {
+        self.cron.consumed_capacity + self.stats.consumed_capacity + self.event.consumed_capacity
+    }
+}
+
+/// Loads the bot stats for [`StatsRange::LOAD`] along with every bot's settings and queue's record.
+/// The tables are read at the same time and progress for each is reported over `tx`
+pub async fn load_all(source: &dyn BotmonSource, tx: &UnboundedSender<LoadMessage>) -> color_eyre::Result<(Vec<BotDynamoStatsRecord>, Vec<BotSettings>, Vec<QueueRecord>, LoadProgress)> {
+    let bucket = StatsRange::LOAD.bucket();
+
+    let progress = |table: LoadTable| move |summary: &QuerySummary| {
+        let _ = tx.send(LoadMessage::Progress { table, summary: *summary });
+    };
+
+    let mut stats_progress = progress(LoadTable::Stats);
+    let mut cron_progress = progress(LoadTable::Cron);
+    let mut event_progress = progress(LoadTable::Event);
+
+    let (stats, bots, queues) = tokio::join!(
+        source.stats_for_period(bucket, &mut stats_progress),
+        source.bot_settings(&mut cron_progress),
+        source.queue_records(&mut event_progress),
+    );
+    let ((stats, stats_summary), (bots, cron_summary)) = (stats?, bots?);
+
+    // Queue records only feed read lag and queue heads, the bus is still worth showing without them
+    let (queues, event_summary, error) = match queues {
+        Ok((queues, summary)) => (queues, summary, None),
+        Err(e) => (vec![], QuerySummary::default(), Some(format!("{} not loaded ({e})", source.queues_location()))),
+    };
+
+    Ok((stats, bots, queues, LoadProgress { cron: cron_summary, stats: stats_summary, event: event_summary, error }))
+}
+
+/// Runs [`load_all`] on a tokio task and reports the result over `tx`
+pub fn spawn_load(source: Arc<dyn BotmonSource>, tx: UnboundedSender<LoadMessage>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let message = match load_all(source.as_ref(), &tx).await {
+            Ok((stats, bots, queues, progress)) => LoadMessage::Loaded { stats, bots, queues, progress },
+            Err(e) => LoadMessage::Failed(format!("{e:#}")),
+        };
+        // The receiver only goes away when the app is shutting down
+        let _ = tx.send(message);
+    })
+}
+
+/// Fetches a single bot's stats over `range` on a tokio task and reports the result over `tx`
+pub fn spawn_bot_history(source: Arc<dyn BotmonSource>, bot_id: String, range: StatsRange, tx: UnboundedSender<LoadMessage>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let stats = source.stats_for_bot(&bot_id, range.bucket()).await
+            .map(|(stats, _)| stats)
+            .map_err(|e| format!("{e:#}"));
+        let _ = tx.send(LoadMessage::BotHistory { bot_id, range, stats });
+    })
+}
+
+/// Fetches every bot's stats over `range` on a tokio task and reports the result over `tx`
+pub fn spawn_range_stats(source: Arc<dyn BotmonSource>, range: StatsRange, tx: UnboundedSender<LoadMessage>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let stats = source.stats_for_period(range.bucket(), &mut |_| {}).await
+            .map(|(stats, _)| stats)
+            .map_err(|e| format!("{e:#}"));
+        let _ = tx.send(LoadMessage::RangeStats { range, stats });
+    })
+}
+
+/// Writes a confirmed action to the bus on a tokio task and reports the result over `tx`
+pub fn spawn_bot_action(source: Arc<dyn BotmonSource>, pending: PendingAction, now: i64, tx: UnboundedSender<LoadMessage>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let result = source.apply_action(&pending, now).await
+            .map_err(|e| format!("{e:#}"));
+        let _ = tx.send(LoadMessage::ActionDone { bot_id: pending.bot.id, action: pending.action, checkpoint: pending.checkpoint, result });
+    })
+}
+
+/// Fetches a page of events on `queue` on a tokio task and reports the result over `tx`
+pub fn spawn_stream_events(source: Arc<dyn BotmonSource>, queue: String, position: StreamPosition, tx: UnboundedSender<LoadMessage>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let events = source.stream_events(&queue, &position, PAGE_SIZE).await
+            .map(|(events, _)| events)
+            .map_err(|e| format!("{e:#}"));
+        let _ = tx.send(LoadMessage::StreamEvents { queue, position, events });
+    })
+}
+
+/// Reads the system or settings table on a tokio task and reports the result over `tx`
+pub fn spawn_entries(source: Arc<dyn BotmonSource>, table: EntryTable, tx: UnboundedSender<LoadMessage>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let entries = source.entries(table).await
+            .map(|(entries, _)| entries)
+            .map_err(|e| format!("{e:#}"));
+        let _ = tx.send(LoadMessage::Entries { table, entries });
+    })
+}
@@ -1,12 +1,21 @@
-use botmon_cli::{app::AppState, error::install_hooks, init, restore, AppParams};
+use botmon_cli::{app::AppState, cli, error::install_hooks, init, restore, AppParams};
 
 #[tokio::main]
 async fn main() -> color_eyre::Result<()>{
     let args: AppParams = argh::from_env();
+    
+    // Headless commands print straight to stdout and never touch the alternate screen
+    if let Some(command) = args.command.as_ref() {
+        color_eyre::install()?;
+        return cli::run(&args, command).await;
+    }
+    
     install_hooks()?;
     let mut terminal = init()?;
-    let mut app = AppState::new(&args).await?;
-    let app_result = app.run(&mut terminal).await;
+    let app_result = match AppState::new(&args).await {
+        Ok(mut app) => app.run(&mut terminal).await,
+        Err(e) => Err(e),
+    };
     restore()?;
     app_result
 }
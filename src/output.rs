@@ -0,0 +1,298 @@
+use std::{io::Write, str::FromStr};
+
+use serde::Serialize;
+
+use crate::{bot_stats::{CondensedStats, QueueStats}, pages::{bot::BotSettings, queue::{QueueConnection, QueueRecord, QueueViewState}}, stream::StreamEvent, ui::{format_lag, format_timestamp}};
+
+/// How headless commands print their results
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OutputFormat {
+    Json,
+    Csv,
+    #[default]
+    Table,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "table" => Ok(OutputFormat::Table),
+            a => Err(format!("unknown format '{a}', expected one of json, csv or table")),
+        }
+    }
+}
+
+/// Something that can be flattened into rows for csv and table output
+pub trait Tabular {
+    fn headers() -> Vec<&'static str>;
+    fn rows(&self) -> Vec<Vec<String>>;
+}
+
+/// Prints a single value, json output is an object
+pub fn write_one<T: Serialize + Tabular>(out: &mut impl Write, format: OutputFormat, value: &T) -> color_eyre::Result<()> {
+    match format {
+        OutputFormat::Json => writeln!(out, "{}", serde_json::to_string_pretty(value)?)?,
+        _ => write_rows(out, format, T::headers(), value.rows())?,
+    }
+    Ok(())
+}
+
+/// Prints a list of values, json output is an array
+pub fn write_many<T: Serialize + Tabular>(out: &mut impl Write, format: OutputFormat, values: &[T]) -> color_eyre::Result<()> {
+    match format {
+        OutputFormat::Json => writeln!(out, "{}", serde_json::to_string_pretty(values)?)?,
+        _ => write_rows(out, format, T::headers(), values.iter().flat_map(|a| a.rows()).collect())?,
+    }
+    Ok(())
+}
+
+fn write_rows(out: &mut impl Write, format: OutputFormat, headers: Vec<&str>, rows: Vec<Vec<String>>) -> color_eyre::Result<()> {
+    match format {
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record(&headers)?;
+            for row in rows {
+                writer.write_record(&row)?;
+            }
+            writer.flush()?;
+        },
+        OutputFormat::Table => {
+            let mut widths: Vec<usize> = headers.iter().map(|a| a.chars().count()).collect();
+            for row in &rows {
+                for (width, cell) in widths.iter_mut().zip(row) {
+                    *width = (*width).max(cell.chars().count());
+                }
+            }
+
+            let header: Vec<String> = headers.iter().map(|a| a.to_uppercase()).collect();
+            write_table_row(out, &widths, &header)?;
+            for row in &rows {
+                write_table_row(out, &widths, row)?;
+            }
+        },
+        OutputFormat::Json => unreachable!("json is written directly with serde"),
+    }
+    Ok(())
+}
+
+fn write_table_row(out: &mut impl Write, widths: &[usize], row: &[String]) -> color_eyre::Result<()> {
+    let line = row.iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{cell:<width$}"))
+        .collect::<Vec<_>>()
+        .join("  ");
+    writeln!(out, "{}", line.trim_end())?;
+    Ok(())
+}
+
+impl Tabular for BotSettings {
+    fn headers() -> Vec<&'static str> {
+        vec!["id", "name", "type", "paused", "error_count", "invoke_time", "lambda_name"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            self.id.clone(),
+            self.name.clone().unwrap_or_default(),
+            self.r_type.clone().unwrap_or_default(),
+            self.paused.unwrap_or_default().to_string(),
+            self.error_count.unwrap_or_default().to_string(),
+            self.invoke_time.map(format_timestamp).unwrap_or_default(),
+            self.lambda_name.clone().unwrap_or_default(),
+        ]]
+    }
+}
+
+/// A bot's merged stats, one row per queue read or written for csv and table output
+#[derive(Debug, Serialize)]
+pub struct BotStatsReport<'a> {
+    pub bot: &'a str,
+    #[serde(flatten)]
+    pub stats: &'a CondensedStats,
+}
+
+impl Tabular for BotStatsReport<'_> {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "bot", "completions", "errors", "units", "duration", "min_duration", "max_duration",
+            "direction", "queue", "queue_units", "checkpoint", "source_timestamp", "timestamp",
+        ]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let execution = &self.stats.execution_stats;
+        let base = vec![
+            self.bot.to_owned(),
+            execution.completions.to_string(),
+            execution.errors.to_string(),
+            execution.units.to_string(),
+            execution.duration.to_string(),
+            execution.min_duration.to_string(),
+            execution.max_duration.to_string(),
+        ];
+
+        let mut queues: Vec<(&str, &String, &QueueStats)> = self.stats.read.iter()
+            .map(|(queue, stats)| ("read", queue, stats))
+            .chain(self.stats.write.iter().map(|(queue, stats)| ("write", queue, stats)))
+            .collect();
+        queues.sort_by(|a, b| a.0.cmp(b.0).then(a.1.cmp(b.1)));
+
+        if queues.is_empty() {
+            return vec![[base, vec![String::new(); 6]].concat()];
+        }
+
+        queues.into_iter()
+            .map(|(direction, queue, stats)| [base.clone(), vec![direction.to_owned(), queue.clone()], queue_stats_cells(stats)].concat())
+            .collect()
+    }
+}
+
+/// Everything known about a queue, a row for its head then one per bot reading or writing it for csv and table output
+#[derive(Debug, Serialize)]
+pub struct QueueReport<'a> {
+    pub queue: &'a str,
+    pub events_written: u64,
+    pub latest_checkpoint: Option<String>,
+    pub latest_source_timestamp: Option<i64>,
+    /// The newest event id on the queue, from its record or the latest write
+    pub head: Option<String>,
+    pub last_written: Option<i64>,
+    /// The queue's record in the event table
+    pub record: Option<&'a QueueRecord>,
+    pub writers: &'a [QueueConnection],
+    pub readers: &'a [QueueConnection],
+}
+
+impl<'a> QueueReport<'a> {
+    pub fn new(view: &'a QueueViewState) -> Self {
+        let latest = view.latest_write();
+        Self {
+            queue: &view.name,
+            events_written: view.events_written(),
+            latest_checkpoint: latest.and_then(|a| a.checkpoint.clone()),
+            latest_source_timestamp: latest.map(|a| a.source_timestamp),
+            head: view.head(),
+            last_written: view.last_written(),
+            record: view.record.as_ref(),
+            writers: &view.writers,
+            readers: &view.readers,
+        }
+    }
+}
+
+impl Tabular for QueueReport<'_> {
+    fn headers() -> Vec<&'static str> {
+        vec!["queue", "direction", "bot", "units", "checkpoint", "source_timestamp", "timestamp", "time_behind", "event_lag"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let head = self.record.map(|record| vec![
+            self.queue.to_owned(),
+            "head".to_owned(),
+            record.owner.clone().unwrap_or_default(),
+            String::new(),
+            self.head.clone().unwrap_or_default(),
+            String::new(),
+            self.last_written.map(format_timestamp).unwrap_or_default(),
+            String::new(),
+            String::new(),
+        ]);
+        let writers = self.writers.iter().map(|a| ("write", a));
+        let readers = self.readers.iter().map(|a| ("read", a));
+
+        head.into_iter().chain(writers.chain(readers)
+            .map(|(direction, connection)| {
+                let lag = match direction {
+                    "read" => format_lag(connection.lag.as_ref()).to_vec(),
+                    _ => vec![String::new(); 2],
+                };
+                [
+                    vec![self.queue.to_owned(), direction.to_owned(), connection.bot.clone()],
+                    queue_stats_cells(&connection.stats),
+                    lag,
+                ].concat()
+            }))
+            .collect()
+    }
+}
+
+impl Tabular for StreamEvent {
+    fn headers() -> Vec<&'static str> {
+        vec!["eid", "timestamp", "event_source_timestamp", "bot", "correlation_id", "payload"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let payload = match (self.payload.as_ref(), self.s3.as_ref()) {
+            (Some(payload), _) => payload.to_string(),
+            (None, Some(s3)) => format!("in s3 {s3}"),
+            (None, None) => String::new(),
+        };
+        vec![vec![
+            self.eid.clone(),
+            self.timestamp.map(format_timestamp).unwrap_or_default(),
+            self.event_source_timestamp.map(format_timestamp).unwrap_or_default(),
+            self.id.clone().unwrap_or_default(),
+            self.correlation_id.as_ref().map(|a| a.to_string()).unwrap_or_default(),
+            payload,
+        ]]
+    }
+}
+
+fn queue_stats_cells(stats: &QueueStats) -> Vec<String> {
+    vec![
+        stats.units.to_string(),
+        stats.checkpoint.clone().unwrap_or_default(),
+        format_timestamp(stats.source_timestamp),
+        format_timestamp(stats.timestamp),
+    ]
+}
+
+#[cfg(test)]
+mod output_tests {
+    use serde::Serialize;
+
+    use super::{write_many, OutputFormat, Tabular};
+
+    #[derive(Serialize)]
+    struct Row {
+        name: &'static str,
+        count: u32,
+    }
+
+    impl Tabular for Row {
+        fn headers() -> Vec<&'static str> {
+            vec!["name", "count"]
+        }
+
+        fn rows(&self) -> Vec<Vec<String>> {
+            vec![vec![self.name.to_owned(), self.count.to_string()]]
+        }
+    }
+
+    fn render(format: OutputFormat) -> String {
+        let rows = [Row { name: "short", count: 1 }, Row { name: "a, longer name", count: 200 }];
+        let mut out = vec![];
+        write_many(&mut out, format, &rows).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn table_columns_are_aligned() {
+        assert_eq!(render(OutputFormat::Table), "NAME            COUNT\nshort           1\na, longer name  200\n");
+    }
+
+    #[test]
+    fn csv_is_quoted() {
+        assert_eq!(render(OutputFormat::Csv), "name,count\nshort,1\n\"a, longer name\",200\n");
+    }
+
+    #[test]
+    fn json_is_an_array() {
+        let json: serde_json::Value = serde_json::from_str(&render(OutputFormat::Json)).unwrap();
+        assert_eq!(json[1]["count"], 200);
+    }
+}
@@ -1,15 +1,18 @@
-use std::{collections::HashMap, fs::File, io::Write};
+use std::collections::HashMap;
 
-use chrono::Duration;
+use chrono::Utc;
 use color_eyre::eyre::{bail, Result};
-use crossterm::event::{Event, KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent};
 use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use itertools::Itertools;
 use ratatui::widgets::ScrollbarState;
 use serde::{Deserialize, Serialize};
-use tui_input::{backend::crossterm::EventHandler, Input};
-use std::fs::read_to_string;
+use serde_json::Value;
+use tui_input::Input;
 
-use crate::{app::Navigate, bot_stats::{BotDynamoStatsRecord, BotStats, QueueStats, StatsOrEmpty}};
+use crate::{app::Navigate, bot_stats::{BotDynamoStatsRecord, ExecutionSeries, QueueStats, StatsOrEmpty}, dynamo::StatsRange, health::{assess_all, Health, HealthThresholds}, instance_log::{deserialize_lenient, GzippedLog}, lag::{read_lags, ReadLag}, pages::queue::QueueRecord};
+
+use super::{inspector::SettingsInspector, logs::LogPaneState};
 
 #[derive(Debug)]
 pub struct BotViewState {
@@ -19,28 +22,71 @@ pub struct BotViewState {
    pub full_stats: Vec<BotDynamoStatsRecord>,
    pub write_stats: HashMap<String, Vec<QueueStats>>,
    pub read_stats: HashMap<String, Vec<QueueStats>>,
+   /// How far behind the bot is on each queue it reads, by queue name
+   pub read_lags: HashMap<String, ReadLag>,
+   pub series: ExecutionSeries,
+   /// The range `history` is fetched for
+   pub range: StatsRange,
+   /// Stats for just this bot over `range`, fetched once the view is opened
+   pub history: Option<Vec<BotDynamoStatsRecord>>,
+   pub history_error: Option<String>,
+   pub health: Option<Health>,
+   pub inspector: SettingsInspector,
+   /// The instance logs, shown in place of the settings while they're open
+   pub logs: Option<LogPaneState>,
    // pub read_connections: Vec<Connection>,
    // pub write_connections: Vec<Connection>
 }
 
-#[derive(Debug)]
-pub struct Connection {
-    name: String,
-    num_actions: u32,
-}
-
 impl BotViewState {
-    pub fn new(setting: BotSettings, stats: Vec<BotDynamoStatsRecord>) -> Self {
+    pub fn new(setting: BotSettings, stats: Vec<BotDynamoStatsRecord>, read_lags: HashMap<String, ReadLag>) -> Self {
         Self {
             vertical_scroll_state: ScrollbarState::default(),
             vertical_scroll: 0,
             setting,
+            read_lags,
             write_stats: Self::write_stats_from_all_stats(&stats),
             read_stats: Self::read_stats_from_all_stats(&stats),
+            series: ExecutionSeries::from_stats(&stats),
             full_stats: stats,
+            range: StatsRange::BOT_HISTORY,
+            history: None,
+            history_error: None,
+            health: None,
+            inspector: SettingsInspector::default(),
+            logs: None,
         }
     }
     
+    /// Swaps in freshly loaded data while keeping the current scroll position
+    pub fn refresh(&mut self, setting: BotSettings, stats: Vec<BotDynamoStatsRecord>, read_lags: HashMap<String, ReadLag>) {
+        self.read_lags = read_lags;
+        self.write_stats = Self::write_stats_from_all_stats(&stats);
+        self.read_stats = Self::read_stats_from_all_stats(&stats);
+        if self.history.is_none() {
+            self.series = ExecutionSeries::from_stats(&stats);
+        }
+        self.full_stats = stats;
+        if let Some(logs) = self.logs.as_mut() {
+            logs.refresh(&setting);
+        }
+        self.setting = setting;
+    }
+    
+    /// Switches to another range, the history has to be fetched again
+    pub fn set_range(&mut self, range: StatsRange) {
+        self.range = range;
+        self.history = None;
+        self.history_error = None;
+    }
+    
+    /// Charts the bot's own history in place of the bus wide stats
+    pub fn apply_history(&mut self, stats: Vec<BotDynamoStatsRecord>) {
+        self.series = ExecutionSeries::from_stats(&stats);
+        self.history = Some(stats);
+        self.history_error = None;
+    }
+    
     fn write_stats_from_all_stats(stats: &[BotDynamoStatsRecord]) -> HashMap<String, Vec<QueueStats>> {
         let mut write_stats = HashMap::new();
         
@@ -86,9 +132,7 @@ impl Navigate for BotViewState {
                 self.vertical_scroll = self.vertical_scroll.saturating_add(1);
                 self.vertical_scroll_state = self.vertical_scroll_state.position(self.vertical_scroll);
             }
-            a => {
-                bail!("invalid key {a:?} pressed");
-            }
+            _ => {}
         }
         Ok(())
     }
@@ -104,6 +148,12 @@ pub struct BotPageState {
     pub all_bots: Option<Vec<BotSettings>>,
     pub search: Input,
     pub search_results: Vec<String>,
+    /// Every bot's health by name, scored on each load
+    pub health: HashMap<String, Health>,
+    /// The selected bus's limits for scoring health
+    pub thresholds: HealthThresholds,
+    /// Every queue's record in the event table, for the heads of queues nothing wrote to in the loaded stats
+    pub queue_records: Vec<QueueRecord>,
     // settings: 
 }
 
@@ -152,36 +202,77 @@ impl BotPageState {
         }
         
         // Sort the matches by score descending
-        matches.sort_by(|a, b| b.1.cmp(&a.1));
+        matches.sort_by_key(|a| std::cmp::Reverse(a.1));
         
         self.search_results = matches.iter().map(|a| a.0.to_owned()).collect();
     }
     
+    /// Replaces the loaded stats and settings with a newer load without losing the
+    /// current search, selection or the bot being viewed. When the viewed bot is no longer
+    /// in the load its last loaded view is kept and the reason is returned
+    pub fn apply_refresh(&mut self, stats: Vec<BotDynamoStatsRecord>, bots: Vec<BotSettings>, queue_records: Vec<QueueRecord>) -> Option<String> {
+        self.health = assess_all(&bots, &stats, &queue_records, &self.thresholds, Utc::now().timestamp_millis())
+            .into_iter()
+            .map(|(id, health)| (id.replace("bot:", ""), health))
+            .collect();
+        self.stats = stats;
+        self.all_bots = Some(bots);
+        self.queue_records = queue_records;
+        self.bot_names();
+        
+        if !self.search.value().is_empty() {
+            self.search_bots();
+        }
+        self.current_select_index = self.current_select_index.min(self.search_results.len().saturating_sub(1));
+        
+        let (Some(view), Some(selected)) = (self.selected_bot.as_mut(), self.selected_bot_name.as_ref()) else {
+            return None;
+        };
+        match Self::details_for(self.all_bots.as_deref().unwrap_or_default(), &self.stats, &self.queue_records, selected) {
+            Ok((settings, bot_stats, read_lags)) => {
+                view.refresh(settings, bot_stats, read_lags);
+                view.health = self.health.get(selected).cloned();
+                None
+            },
+            Err(_) => Some(format!("{selected} is gone from the bus, showing it as last loaded")),
+        }
+    }
+    
+    fn details_for(all_bots: &[BotSettings], stats: &[BotDynamoStatsRecord], queue_records: &[QueueRecord], selected: &str) -> Result<(BotSettings, Vec<BotDynamoStatsRecord>, HashMap<String, ReadLag>)> {
+        let bot_stats: Vec<BotDynamoStatsRecord> = stats.iter()
+            .filter(|a| a.id.contains(selected) && a.period == "minute_15")
+            .cloned()
+            .collect();
+        let settings = match all_bots.iter().find(|&a| a.id.contains(selected)).cloned() {
+            Some(a) => a,
+            None => bail!("unable to locate settings for selected bot '{selected}'"),
+        };
+        // Lag needs every writer's checkpoints to find the head of each queue
+        let read_lags = read_lags(all_bots, stats, queue_records, Utc::now().timestamp_millis())
+            .into_iter()
+            .filter(|((bot, _), _)| *bot == settings.id)
+            .map(|((_, queue), lag)| (queue, lag))
+            .collect();
+        
+        Ok((settings, bot_stats, read_lags))
+    }
+    
     pub fn get_bot_details(&mut self)-> Result<()> {
         match (self.all_bots.as_ref(), self.selected_bot_name.as_ref()) {
             (None, None) => bail!("no bots loaded AND no bot selected"),
             (None, Some(_)) => bail!("no bots loaded"),
             (Some(_), None) => bail!("no bot selected when attempting to get bot details"),
             (Some(all_bots), Some(selected)) => {
-                // Load bots stats from file. Eventually this will be loaded directly from dynamo
-                // let all_stats = read_to_string("./all_bot_stats.json")?;
-                // let stats: Vec<BotDynamoStatsRecord> = serde_json::from_str(&all_stats)?;
-                
-                let bot_stats: Vec<BotDynamoStatsRecord> = self.stats.iter()
-                    .filter(|a| a.id.contains(selected) && a.period == "minute_15")
-                    .cloned()
-                    .collect();
-                let settings = match all_bots.iter().find(|&a| a.id.contains(selected)).cloned() {
-                    Some(a) => a,
-                    None => bail!("unable to locate settings for selected bot '{selected}'"),
-                };
+                let (settings, bot_stats, read_lags) = Self::details_for(all_bots, &self.stats, &self.queue_records, selected)?;
                 
                 //REMOVE THE BELOW
                 // let stats_filename = format!("./{}.json", selected);
                 // let mut file = File::create(&stats_filename)?;
                 // file.write_all(serde_json::to_string(&bot_stats)?.as_bytes())?;
                 // DON'T REMOVE BELOW
-                self.selected_bot = Some(BotViewState::new(settings, bot_stats));
+                let mut view = BotViewState::new(settings, bot_stats, read_lags);
+                view.health = self.health.get(selected).cloned();
+                self.selected_bot = Some(view);
             },
         };
         
@@ -242,60 +333,108 @@ impl BotPageState {
 #[serde(rename_all="camelCase")]
 pub struct BotSettings {
     pub id: String,
-    checkpoints: Option<Checkpoints>,
-    description: Option<String>,
-    error_count: Option<u32>,
-    execution_type: Option<String>,
-    instances: Option<HashMap<String, Instance>>,
-    invoke_time: Option<i64>,
-    lambda_name: Option<String>,
-    message: Option<String>,
-    name: Option<String>,
-    paused: Option<bool>,
-    progress: Option<HashMap<String, String>>,
+    pub checkpoints: Option<Checkpoints>,
+    pub description: Option<String>,
+    pub error_count: Option<u32>,
+    pub execution_type: Option<String>,
+    pub instances: Option<HashMap<String, Instance>>,
+    pub invoke_time: Option<i64>,
+    pub lambda_name: Option<String>,
+    pub message: Option<String>,
+    pub name: Option<String>,
+    pub paused: Option<bool>,
+    pub progress: Option<HashMap<String, String>>,
     #[serde(rename="requested_kinesis")]
-    requested_kinesis: Option<HashMap<String, String>>,
-    scheduled_trigger: Option<i64>,
-    tags: Option<String>, // comma-delimited-list
-    token: Option<i64>,
-    trigger: Option<i64>,
-    triggers: Option<Vec<String>>,
+    pub requested_kinesis: Option<HashMap<String, String>>,
+    pub scheduled_trigger: Option<i64>,
+    /// Whatever the bot is configured with, including the systems it loads from or sends to
+    pub settings: Option<Value>,
+    pub tags: Option<String>, // comma-delimited-list
+    pub token: Option<i64>,
+    pub trigger: Option<i64>,
+    pub triggers: Option<Vec<String>>,
     #[serde(rename="type")]
-    r_type: Option<String>, // Eventual enum
+    pub r_type: Option<String>, // Eventual enum
 }
+impl BotSettings {
+    /// The systems the bot connects to, without the `system:` prefix. These are the checkpoints,
+    /// triggers and settings values that start with `system:`
+    pub fn systems(&self) -> Vec<String> {
+        fn collect(value: &Value, systems: &mut Vec<String>) {
+            match value {
+                Value::String(a) if a.starts_with("system:") => systems.push(a.clone()),
+                Value::Array(values) => values.iter().for_each(|a| collect(a, systems)),
+                Value::Object(map) => map.values().for_each(|a| collect(a, systems)),
+                _ => {},
+            }
+        }
+
+        let mut systems: Vec<String> = self.checkpoints.iter()
+            .flat_map(|a| [a.read.as_ref(), a.write.as_ref()])
+            .flatten()
+            .flat_map(|a| a.keys())
+            .chain(self.triggers.iter().flatten())
+            .filter(|a| a.starts_with("system:"))
+            .cloned()
+            .collect();
+        if let Some(settings) = self.settings.as_ref() {
+            collect(settings, &mut systems);
+        }
+
+        systems.iter()
+            .map(|a| a.replace("system:", ""))
+            .sorted()
+            .dedup()
+            .collect()
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Checkpoints {
-    read: Option<HashMap<String, CheckpointDetail>>,
-    write: Option<HashMap<String, CheckpointDetail>>
+    pub read: Option<HashMap<String, CheckpointDetail>>,
+    pub write: Option<HashMap<String, CheckpointDetail>>
 }
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all="snake_case")]
 pub struct CheckpointDetail {
-    checkpoint: Option<StrOrNum>,
-    ended_timestamp: Option<StrOrNum>,
-    records: Option<u32>,
-    source_timestamp: Option<StrOrNum>,
+    pub checkpoint: Option<StrOrNum>,
+    pub ended_timestamp: Option<StrOrNum>,
+    pub records: Option<u32>,
+    pub source_timestamp: Option<StrOrNum>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all="camelCase")]
 pub struct Instance {
-    completed_time: Option<i64>,
-    invoke_time: Option<i64>,
-    // Will need to be unzipped
-    // log: Vec<u8>
-    max_duration: Option<u32>,
-    request_id: Option<StrOrNum>,
-    result: Option<String>,
-    start_time: Option<i64>,
-    status: Option<String>,
-    token: Option<i64>,
+    pub completed_time: Option<i64>,
+    pub invoke_time: Option<i64>,
+    /// Gzipped, unzipped when it's viewed. Anything else is dropped rather than failing the bot
+    #[serde(default, deserialize_with = "deserialize_lenient")]
+    pub log: Option<GzippedLog>,
+    pub max_duration: Option<u32>,
+    pub request_id: Option<StrOrNum>,
+    pub result: Option<String>,
+    pub start_time: Option<i64>,
+    pub status: Option<String>,
+    pub token: Option<i64>,
+}
+
+impl Instance {
+    /// How long the run took, or has been going for when it's still running at `now`
+    pub fn duration(&self, now: i64) -> Option<i64> {
+        let start = self.start_time?;
+        match self.completed_time {
+            Some(completed) if completed >= start => Some(completed - start),
+            _ if self.status.as_deref() == Some("running") => Some(now - start),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all="camelCase")]
 pub struct Lambda {
-    settings: Vec<HashMap<String, String>>
+    pub settings: Vec<HashMap<String, String>>
 }
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(untagged)]
@@ -303,4 +442,39 @@ pub enum StrOrNum {
     String(String),
     Num(i64)
     
-}
\ No newline at end of file
+}
+impl StrOrNum {
+    /// The value as epoch millis, parsing it when it was stored as a string
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            StrOrNum::String(a) => a.parse().ok(),
+            StrOrNum::Num(a) => Some(*a),
+        }
+    }
+}
+
+impl std::fmt::Display for StrOrNum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StrOrNum::String(a) => write!(f, "{a}"),
+            StrOrNum::Num(a) => write!(f, "{a}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod bot_page_tests {
+    use super::{BotPageState, BotSettings};
+
+    #[test]
+    fn a_viewed_bot_that_disappears_keeps_its_last_view() {
+        let bots: Vec<BotSettings> = serde_json::from_str(r#"[{"id": "bot:a"}, {"id": "bot:b"}]"#).unwrap();
+        let mut page = BotPageState::default();
+        assert_eq!(page.apply_refresh(vec![], bots.clone(), vec![]), None);
+
+        page.selected_bot_name = Some("a".to_string());
+        page.get_bot_details().unwrap();
+        assert_eq!(page.apply_refresh(vec![], bots[1..].to_vec(), vec![]).as_deref(), Some("a is gone from the bus, showing it as last loaded"));
+        assert_eq!(page.selected_bot.map(|a| a.setting.id).as_deref(), Some("bot:a"));
+    }
+}
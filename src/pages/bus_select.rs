@@ -1,11 +1,9 @@
 use std::collections::HashMap;
 
-use chrono::NaiveDate;
-use crossterm::event::{KeyCode, KeyEvent};
 use itertools::Itertools;
 use ratatui::widgets::ScrollbarState;
 
-use crate::{app::Navigate, leo_config::LeoConfig};
+use crate::leo_config::LeoConfig;
 
 #[derive(Debug)]
 pub struct BusSelectState {
@@ -18,7 +16,7 @@ pub struct BusSelectState {
 impl BusSelectState {
     pub fn new(buses: &HashMap<String, LeoConfig>) -> Self {
         Self {
-            buses: buses.keys().map(|a| a.clone()).sorted().collect(),
+            buses: buses.keys().cloned().sorted().collect(),
             bus_selected_index: 0,
             vertical_scroll: 0,
             vertical_scroll_state: ScrollbarState::default(),
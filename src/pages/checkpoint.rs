@@ -0,0 +1,236 @@
+use std::{collections::HashMap, str::FromStr};
+
+use chrono::{DateTime, NaiveDateTime};
+use color_eyre::eyre::bail;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tui_input::{backend::crossterm::EventHandler, Input};
+
+use crate::{actions::{AuditLog, BotAction, CheckpointChange, PendingAction}, bot_stats::BotDynamoStatsRecord, event_id::EventId, lag::queue_heads, ui::format_duration};
+
+use super::{bot::BotSettings, queue::{queue_name, QueueRecord}};
+
+/// Where a read checkpoint is being moved to, as typed in
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckpointTarget {
+    EventId(String),
+    /// Epoch millis, reading starts with the first event written at or after it
+    Time(i64),
+    /// The newest event written to the queue, skipping everything not read yet
+    Head,
+    /// Where the checkpoint was before botmon last moved it
+    Undo,
+}
+
+impl FromStr for CheckpointTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let time = |a: &str| {
+            DateTime::parse_from_rfc3339(a).map(|a| a.timestamp_millis())
+                .or_else(|_| NaiveDateTime::parse_from_str(a, "%Y-%m-%d %H:%M:%S").map(|a| a.and_utc().timestamp_millis()))
+                .or_else(|_| NaiveDateTime::parse_from_str(a, "%Y-%m-%d %H:%M").map(|a| a.and_utc().timestamp_millis()))
+        };
+
+        match s {
+            "head" => Ok(CheckpointTarget::Head),
+            "undo" => Ok(CheckpointTarget::Undo),
+            a if a.starts_with("z/") => match EventId::parse(a) {
+                Some(_) => Ok(CheckpointTarget::EventId(a.to_owned())),
+                None => Err(format!("'{a}' is not a valid event id")),
+            },
+            a if !a.is_empty() && a.chars().all(|c| c.is_ascii_digit()) => a.parse()
+                .map(CheckpointTarget::Time)
+                .map_err(|_| format!("'{a}' is out of range for epoch millis")),
+            a => time(a)
+                .map(CheckpointTarget::Time)
+                .map_err(|_| format!("'{a}' isn't an event id, a time like 2024-07-15 12:30, epoch millis, head or undo")),
+        }
+    }
+}
+
+/// Which part of the editor has focus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckpointStep {
+    Queue,
+    Target,
+    Confirm,
+}
+
+/// Moves one of a bot's read checkpoints: pick the queue, say where to, then type the bot's id to confirm
+#[derive(Debug)]
+pub struct CheckpointEditor {
+    pub bot: BotSettings,
+    pub bus: String,
+    /// The bot's read checkpoints by queue key, as loaded
+    pub queues: Vec<(String, Option<String>)>,
+    /// The newest event id on each queue by name
+    pub heads: HashMap<String, String>,
+    pub selected: usize,
+    pub step: CheckpointStep,
+    pub target: Input,
+    pub change: Option<CheckpointChange>,
+    pub confirm: Input,
+    pub error: Option<String>,
+}
+
+impl CheckpointEditor {
+    pub fn new(bot: BotSettings, bus: &str, bots: &[BotSettings], stats: &[BotDynamoStatsRecord], records: &[QueueRecord]) -> color_eyre::Result<Self> {
+        let mut queues: Vec<(String, Option<String>)> = bot.checkpoints.as_ref()
+            .and_then(|a| a.read.as_ref())
+            .into_iter()
+            .flatten()
+            .map(|(queue, detail)| (queue.clone(), detail.checkpoint.as_ref().map(|a| a.to_string())))
+            .collect();
+        if queues.is_empty() {
+            bail!("{} has no read checkpoints to move", bot.id);
+        }
+        queues.sort();
+
+        Ok(Self {
+            heads: queue_heads(bots, stats, records),
+            bus: bus.to_owned(),
+            bot,
+            queues,
+            selected: 0,
+            step: CheckpointStep::Queue,
+            target: Input::default(),
+            change: None,
+            confirm: Input::default(),
+            error: None,
+        })
+    }
+
+    /// The name that has to be typed to confirm, the bot id without its `bot:` prefix
+    pub fn confirm_name(&self) -> String {
+        self.bot.id.replace("bot:", "")
+    }
+
+    /// Works out the new checkpoint for the selected queue
+    fn resolve(&self, target: &CheckpointTarget, audit_log: &AuditLog) -> Result<CheckpointChange, String> {
+        let (queue, before) = self.queues[self.selected].clone();
+        let after = match target {
+            CheckpointTarget::EventId(eid) => eid.clone(),
+            CheckpointTarget::Time(time) => EventId::before_timestamp(*time)
+                .ok_or_else(|| format!("{time} is out of range"))?,
+            CheckpointTarget::Head => self.heads.get(&queue_name(&queue))
+                .cloned()
+                .ok_or_else(|| format!("the head of {queue} isn't known, nothing has written to it in the loaded stats and it has no record in the event table"))?,
+            CheckpointTarget::Undo => audit_log.last_undo(&self.bus, &self.bot.id, &queue)
+                .map_err(|e| format!("{e:#}"))?
+                .and_then(|a| a.change.before)
+                .ok_or_else(|| format!("there is no earlier checkpoint recorded for {queue} in {}", audit_log.undo_path.display()))?,
+        };
+        if before.as_ref() == Some(&after) {
+            return Err(format!("the checkpoint on {queue} is already {after}"));
+        }
+
+        Ok(CheckpointChange { queue, before, after })
+    }
+
+    /// Goes back a step, returning true when there's nothing to go back to and the editor should close
+    pub fn back(&mut self) -> bool {
+        self.error = None;
+        match self.step {
+            CheckpointStep::Queue => return true,
+            CheckpointStep::Target => self.step = CheckpointStep::Queue,
+            CheckpointStep::Confirm => {
+                self.confirm.reset();
+                self.change = None;
+                self.step = CheckpointStep::Target;
+            },
+        }
+        false
+    }
+
+    /// Handles a key for the current step, returning the write once the bot id has been typed in to confirm it
+    pub fn handle_key(&mut self, key_event: KeyEvent, audit_log: &AuditLog) -> Option<PendingAction> {
+        let len = self.queues.len();
+        match (self.step, key_event.code) {
+            (CheckpointStep::Queue, KeyCode::Down) => self.selected = (self.selected + 1) % len,
+            (CheckpointStep::Queue, KeyCode::Up) => self.selected = (self.selected + len - 1) % len,
+            (CheckpointStep::Queue, KeyCode::Enter) => {
+                self.error = None;
+                self.step = CheckpointStep::Target;
+            },
+            (CheckpointStep::Queue, _) => {},
+            (CheckpointStep::Target, KeyCode::Enter) => {
+                let change = self.target.value().parse().and_then(|a| self.resolve(&a, audit_log));
+                match change {
+                    Ok(change) => {
+                        self.change = Some(change);
+                        self.error = None;
+                        self.step = CheckpointStep::Confirm;
+                    },
+                    Err(e) => self.error = Some(e),
+                }
+            },
+            (CheckpointStep::Confirm, KeyCode::Enter) => {
+                let name = self.confirm_name();
+                if self.confirm.value() != name {
+                    self.error = Some(format!("type {name} to confirm"));
+                    return None;
+                }
+                return Some(PendingAction {
+                    action: BotAction::Checkpoint,
+                    bot: self.bot.clone(),
+                    checkpoint: self.change.clone(),
+                });
+            },
+            (CheckpointStep::Target, _) => {
+                self.target.handle_event(&Event::Key(key_event));
+            },
+            (CheckpointStep::Confirm, _) => {
+                self.confirm.handle_event(&Event::Key(key_event));
+            },
+        }
+        None
+    }
+}
+
+/// How far a checkpoint moves and what that means for the bot
+pub fn describe_move(change: &CheckpointChange) -> String {
+    let before = change.before.as_deref().and_then(EventId::parse);
+    let after = EventId::parse(&change.after);
+    match before.zip(after) {
+        Some((before, after)) if after < before => format!(
+            "rewinds {}, events after the new checkpoint will be read again",
+            format_duration(before.timestamp - after.timestamp),
+        ),
+        Some((before, after)) => format!(
+            "fast-forwards {}, events before the new checkpoint will be skipped",
+            format_duration(after.timestamp - before.timestamp),
+        ),
+        None => "sets a checkpoint where there wasn't one".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod checkpoint_tests {
+    use crate::{actions::AuditLog, source::fixtures};
+
+    use super::{describe_move, CheckpointEditor, CheckpointTarget};
+
+    #[test]
+    fn targets_resolve_to_checkpoints() {
+        let (bots, stats) = (fixtures::bots(), fixtures::stats());
+        let enricher = bots.iter().find(|a| a.id == "bot:order_enricher").unwrap().clone();
+        let editor = CheckpointEditor::new(enricher, "local", &bots, &stats, &[]).unwrap();
+        let audit_log = AuditLog::new(Some("./fixtures/missing_audit.jsonl"), None);
+        let resolve = |a: &str| editor.resolve(&a.parse().unwrap(), &audit_log);
+
+        assert_eq!("2024-07-15 12:30".parse(), Ok(CheckpointTarget::Time(1721046600000)));
+        assert_eq!("1721046600000".parse(), Ok(CheckpointTarget::Time(1721046600000)));
+        assert!("z/not/an/eid".parse::<CheckpointTarget>().is_err());
+        assert!("yesterday".parse::<CheckpointTarget>().is_err());
+
+        let rewind = resolve("2024-07-15 11:00").unwrap();
+        assert_eq!(rewind.queue, "queue:orders");
+        assert_eq!(rewind.after, "z/2024/07/15/11/00/1721041200000");
+        assert_eq!(describe_move(&rewind), "rewinds 59m 0s, events after the new checkpoint will be read again");
+
+        let head = resolve("head").unwrap();
+        assert_eq!(Some(&head.after), editor.heads.get("orders"));
+        assert!(resolve("undo").is_err());
+    }
+}
@@ -0,0 +1,206 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tui_input::{backend::crossterm::EventHandler, Input};
+
+use super::bot::BotSettings;
+
+/// How far page up and page down scroll the json
+const DETAIL_PAGE: usize = 10;
+
+/// The tables of loose json entries keyed by id that can be browsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryTable {
+    /// leo_system, the external systems bots load from and send to
+    System,
+    /// leo_settings, bus wide settings
+    Settings,
+}
+
+impl Display for EntryTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EntryTable::System => write!(f, "systems"),
+            EntryTable::Settings => write!(f, "settings"),
+        }
+    }
+}
+
+/// An entry in the system or settings table, kept whole for the detail pane
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableEntry {
+    pub id: String,
+    #[serde(flatten)]
+    pub fields: Map<String, Value>,
+}
+
+impl TableEntry {
+    /// What the entry calls itself besides its id
+    pub fn label(&self) -> Option<&str> {
+        ["label", "name", "type"].into_iter()
+            .find_map(|a| self.fields.get(a)?.as_str())
+    }
+
+    pub fn to_json(&self) -> Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+}
+
+/// Strips the `system:` prefix bots use when they reference a system
+pub fn system_name(id: &str) -> String {
+    id.replace("system:", "")
+}
+
+/// A searchable list of the entries in the system or settings table with the selected one's json alongside
+#[derive(Debug)]
+pub struct EntryBrowserState {
+    pub table: EntryTable,
+    pub entries: Vec<TableEntry>,
+    pub search: Input,
+    /// Indexes into `entries` that match the search, best match first
+    pub results: Vec<usize>,
+    pub selected: usize,
+    pub detail_scroll: usize,
+    /// The bots connected to each system by system name
+    pub links: HashMap<String, Vec<String>>,
+    /// Index into the selected entry's linked bots
+    pub selected_link: usize,
+    pub loading: bool,
+    pub error: Option<String>,
+    /// The entry to select once they load, set when following a link from a bot
+    pub focus: Option<String>,
+}
+
+impl EntryBrowserState {
+    pub fn new(table: EntryTable, bots: &[BotSettings], focus: Option<String>) -> Self {
+        let mut links: HashMap<String, Vec<String>> = HashMap::new();
+        if table == EntryTable::System {
+            for bot in bots {
+                for system in bot.systems() {
+                    links.entry(system).or_default().push(bot.id.clone());
+                }
+            }
+        }
+
+        Self {
+            table,
+            entries: vec![],
+            search: Input::default(),
+            results: vec![],
+            selected: 0,
+            detail_scroll: 0,
+            links,
+            selected_link: 0,
+            loading: true,
+            error: None,
+            focus,
+        }
+    }
+
+    pub fn selected_entry(&self) -> Option<&TableEntry> {
+        self.results.get(self.selected).map(|a| &self.entries[*a])
+    }
+
+    /// The bots connected to the selected entry, only systems have any
+    pub fn linked_bots(&self) -> &[String] {
+        self.selected_entry()
+            .and_then(|a| self.links.get(&system_name(&a.id)))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Shows the loaded entries, selecting the one being followed to
+    pub fn apply(&mut self, result: Result<Vec<TableEntry>, String>) {
+        self.loading = false;
+        match result {
+            Ok(mut entries) => {
+                entries.sort_by(|a, b| a.id.cmp(&b.id));
+                self.entries = entries;
+                self.error = None;
+            },
+            Err(e) => self.error = Some(e),
+        }
+        self.search_entries();
+
+        if let Some(focus) = self.focus.take() {
+            match self.results.iter().position(|a| system_name(&self.entries[*a].id) == system_name(&focus)) {
+                Some(index) => self.select(index),
+                None => self.error = Some(format!("{focus} isn't in the {} table", self.table)),
+            }
+        }
+    }
+
+    /// Fuzzy matches the search against each entry's id and label
+    pub fn search_entries(&mut self) {
+        let value = self.search.value();
+        let matcher = SkimMatcherV2::default();
+        let mut matches: Vec<(usize, i64)> = self.entries.iter()
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                let text = format!("{} {}", entry.id, entry.label().unwrap_or_default());
+                match value.is_empty() {
+                    true => Some((index, 0)),
+                    false => matcher.fuzzy_match(&text, value).map(|score| (index, score)),
+                }
+            })
+            .collect();
+        matches.sort_by_key(|a| std::cmp::Reverse(a.1));
+
+        self.results = matches.into_iter().map(|a| a.0).collect();
+        self.select(self.selected.min(self.results.len().saturating_sub(1)));
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = index;
+        self.detail_scroll = 0;
+        self.selected_link = 0;
+    }
+
+    /// Handles a key, returning the bot to open when a link is followed
+    pub fn handle_key(&mut self, key_event: KeyEvent) -> Option<String> {
+        match key_event.code {
+            KeyCode::Up => self.select(self.selected.saturating_sub(1)),
+            KeyCode::Down => self.select((self.selected + 1).min(self.results.len().saturating_sub(1))),
+            KeyCode::PageUp => self.detail_scroll = self.detail_scroll.saturating_sub(DETAIL_PAGE),
+            KeyCode::PageDown => self.detail_scroll += DETAIL_PAGE,
+            KeyCode::Left => self.selected_link = self.selected_link.saturating_sub(1),
+            KeyCode::Right => self.selected_link = (self.selected_link + 1).min(self.linked_bots().len().saturating_sub(1)),
+            KeyCode::Enter => return self.linked_bots().get(self.selected_link).cloned(),
+            _ => {
+                self.search.handle_event(&Event::Key(key_event));
+                self.select(0);
+                self.search_entries();
+            },
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod entries_tests {
+    use crate::source::fixtures;
+
+    use super::{EntryBrowserState, EntryTable, TableEntry};
+
+    #[test]
+    fn systems_link_to_the_bots_that_use_them() {
+        let bots = fixtures::bots();
+        let systems: Vec<TableEntry> = fixtures::load("leo_system.json");
+        let sync = bots.iter().find(|a| a.id == "bot:legacy_customer_sync").unwrap();
+        assert_eq!(sync.systems(), vec!["legacy_crm".to_string()]);
+
+        let mut browser = EntryBrowserState::new(EntryTable::System, &bots, Some("system:legacy_crm".to_string()));
+        browser.apply(Ok(systems));
+        assert_eq!(browser.selected_entry().map(|a| a.id.as_str()), Some("legacy_crm"));
+        assert_eq!(browser.selected_entry().and_then(|a| a.label()), Some("Legacy CRM database"));
+        assert_eq!(browser.linked_bots(), ["bot:legacy_customer_sync".to_string()]);
+
+        browser.search = "pay".into();
+        browser.search_entries();
+        assert_eq!(browser.selected_entry().map(|a| a.id.as_str()), Some("payments"));
+        assert!(browser.linked_bots().is_empty());
+    }
+}
@@ -0,0 +1,150 @@
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tui_input::{backend::crossterm::EventHandler, Input};
+
+use crate::stream::{StreamEvent, StreamPosition};
+
+use super::bot::BotSettings;
+
+/// How far page up and page down scroll the payload
+const PAYLOAD_PAGE: usize = 10;
+
+/// A page of the events on a queue from the stream table, with the selected event's payload alongside
+#[derive(Debug)]
+pub struct EventBrowserState {
+    /// The queue's name, without the `queue:` prefix
+    pub queue: String,
+    /// Where the page being shown, or being loaded, starts
+    pub position: StreamPosition,
+    pub events: Vec<StreamEvent>,
+    pub selected: usize,
+    pub payload_scroll: usize,
+    /// A bot's read checkpoint on the queue as `(bot id, eid)`, marked in the list
+    pub checkpoint: Option<(String, String)>,
+    pub loading: bool,
+    /// Why the last page didn't load, or that there was nothing past the end
+    pub note: Option<String>,
+    /// The position being typed in, open after `g`
+    pub jump: Option<Input>,
+}
+
+impl EventBrowserState {
+    pub fn new(queue: &str, position: StreamPosition, checkpoint: Option<(String, String)>) -> Self {
+        Self {
+            queue: queue.to_owned(),
+            position,
+            events: vec![],
+            selected: 0,
+            payload_scroll: 0,
+            checkpoint,
+            loading: true,
+            note: None,
+            jump: None,
+        }
+    }
+
+    pub fn selected_event(&self) -> Option<&StreamEvent> {
+        self.events.get(self.selected)
+    }
+
+    /// Shows a fetched page unless the browser has moved on since. Paging past either end keeps the events shown
+    pub fn apply(&mut self, position: StreamPosition, result: Result<Vec<StreamEvent>, String>) {
+        if position != self.position {
+            return;
+        }
+        self.loading = false;
+        self.note = None;
+
+        let events = match result {
+            Ok(events) => events,
+            Err(e) => {
+                self.note = Some(e);
+                return;
+            },
+        };
+        if events.is_empty() {
+            self.note = Some(match position {
+                StreamPosition::After(_) if !self.events.is_empty() => "no newer events".to_string(),
+                StreamPosition::Before(_) if !self.events.is_empty() => "no older events".to_string(),
+                a => {
+                    self.events.clear();
+                    format!("no events on {} {a}", self.queue)
+                },
+            });
+            return;
+        }
+
+        // Opened at a checkpoint, the event after it is the one the bot reads next
+        let next_read = self.checkpoint.as_ref()
+            .filter(|(_, eid)| events[0].eid == *eid && events.len() > 1)
+            .map(|_| 1);
+        self.selected = match position {
+            StreamPosition::Before(_) | StreamPosition::End => events.len() - 1,
+            _ => next_read.unwrap_or_default(),
+        };
+        self.payload_scroll = 0;
+        self.events = events;
+    }
+
+    /// Starts loading the page at `position`, returning it so it can be fetched
+    fn load(&mut self, position: StreamPosition) -> Option<StreamPosition> {
+        self.position = position.clone();
+        self.loading = true;
+        self.note = None;
+        Some(position)
+    }
+
+    /// Handles a key, returning the page to fetch when it moves to another one
+    pub fn handle_key(&mut self, key_event: KeyEvent, bots: &[BotSettings]) -> Option<StreamPosition> {
+        if let Some(jump) = self.jump.as_mut() {
+            match key_event.code {
+                KeyCode::Esc => self.jump = None,
+                KeyCode::Enter => match StreamPosition::parse(jump.value(), &self.queue, bots) {
+                    Ok(position) => {
+                        let value = jump.value().trim().to_owned();
+                        self.checkpoint = match (value.starts_with("bot:"), &position) {
+                            (true, StreamPosition::At(eid)) => Some((value, eid.clone())),
+                            _ => self.checkpoint.take(),
+                        };
+                        self.jump = None;
+                        return self.load(position);
+                    },
+                    Err(e) => self.note = Some(e),
+                },
+                _ => {
+                    jump.handle_event(&Event::Key(key_event));
+                },
+            }
+            return None;
+        }
+
+        match key_event.code {
+            KeyCode::Up => {
+                self.selected = self.selected.saturating_sub(1);
+                self.payload_scroll = 0;
+            },
+            KeyCode::Down => {
+                self.selected = (self.selected + 1).min(self.events.len().saturating_sub(1));
+                self.payload_scroll = 0;
+            },
+            KeyCode::PageUp => self.payload_scroll = self.payload_scroll.saturating_sub(PAYLOAD_PAGE),
+            KeyCode::PageDown => self.payload_scroll += PAYLOAD_PAGE,
+            KeyCode::Right if !self.loading => {
+                let position = self.events.last().map(|a| StreamPosition::After(a.eid.clone()));
+                return position.and_then(|a| self.load(a));
+            },
+            KeyCode::Left if !self.loading => {
+                let position = self.events.first().map(|a| StreamPosition::Before(a.eid.clone()));
+                return position.and_then(|a| self.load(a));
+            },
+            KeyCode::Char('s') => return self.load(StreamPosition::Start),
+            KeyCode::Char('e') => return self.load(StreamPosition::End),
+            KeyCode::Char('r') => return self.load(self.position.clone()),
+            KeyCode::Char('g') => {
+                self.note = None;
+                self.jump = Some(Input::default());
+            },
+            _ => {},
+        }
+        None
+    }
+}
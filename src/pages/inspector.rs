@@ -0,0 +1,67 @@
+use std::collections::HashSet;
+
+/// A collapsible part of the settings inspector
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SettingsSection {
+    Details,
+    Schedule,
+    Triggers,
+    ReadCheckpoints,
+    WriteCheckpoints,
+    Instances,
+}
+
+impl SettingsSection {
+    pub const ALL: [SettingsSection; 6] = [
+        SettingsSection::Details,
+        SettingsSection::Schedule,
+        SettingsSection::Triggers,
+        SettingsSection::ReadCheckpoints,
+        SettingsSection::WriteCheckpoints,
+        SettingsSection::Instances,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            SettingsSection::Details => "Details",
+            SettingsSection::Schedule => "Schedule",
+            SettingsSection::Triggers => "Triggers",
+            SettingsSection::ReadCheckpoints => "Read checkpoints",
+            SettingsSection::WriteCheckpoints => "Write checkpoints",
+            SettingsSection::Instances => "Instances",
+        }
+    }
+}
+
+/// How the bot's settings are shown: sections that can be folded away, or the raw json
+#[derive(Debug, Default)]
+pub struct SettingsInspector {
+    pub raw: bool,
+    /// Index into [`SettingsSection::ALL`]
+    pub selected: usize,
+    pub collapsed: HashSet<SettingsSection>,
+    /// Set when the selection moves so the next render scrolls to it
+    pub follow: bool,
+}
+
+impl SettingsInspector {
+    pub fn section(&self) -> SettingsSection {
+        SettingsSection::ALL[self.selected]
+    }
+
+    /// Moves the selection to the next section, or the previous one when `back`
+    pub fn select(&mut self, back: bool) {
+        let len = SettingsSection::ALL.len();
+        self.selected = if back { (self.selected + len - 1) % len } else { (self.selected + 1) % len };
+        self.follow = true;
+    }
+
+    /// Folds the selected section away or opens it back up
+    pub fn toggle_section(&mut self) {
+        let section = self.section();
+        if !self.collapsed.remove(&section) {
+            self.collapsed.insert(section);
+        }
+        self.follow = true;
+    }
+}
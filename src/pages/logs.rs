@@ -0,0 +1,168 @@
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use tui_input::{backend::crossterm::EventHandler, Input};
+
+use super::bot::{BotSettings, Instance};
+
+/// How many lines page up and page down move the log
+const PAGE: usize = 20;
+
+/// The log each instance of the viewed bot kept from its last run, read one instance at a time
+#[derive(Debug)]
+pub struct LogPaneState {
+    /// The bot's instances by id, sorted
+    pub instances: Vec<(String, Instance)>,
+    pub selected: usize,
+    /// The selected instance's log, only unzipped once it's switched to
+    pub lines: Result<Vec<String>, String>,
+    pub search: Input,
+    /// Indexes of the lines containing the search
+    pub matches: Vec<usize>,
+    /// Which of `matches` was last jumped to
+    pub current_match: Option<usize>,
+    /// The first line shown
+    pub scroll: usize,
+}
+
+impl LogPaneState {
+    pub fn new(bot: &BotSettings) -> Self {
+        let mut state = Self {
+            instances: vec![],
+            selected: 0,
+            lines: Ok(vec![]),
+            search: Input::default(),
+            matches: vec![],
+            current_match: None,
+            scroll: 0,
+        };
+        state.refresh(bot);
+        state
+    }
+
+    /// Picks up reloaded instances, the log is only unzipped again when the selected instance has a new one
+    pub fn refresh(&mut self, bot: &BotSettings) {
+        let previous = self.instances.get(self.selected).cloned();
+        let mut instances: Vec<(String, Instance)> = bot.instances.clone().unwrap_or_default().into_iter().collect();
+        instances.sort_by(|a, b| a.0.cmp(&b.0));
+        self.instances = instances;
+
+        self.selected = previous.as_ref()
+            .and_then(|(id, _)| self.instances.iter().position(|a| a.0 == *id))
+            .unwrap_or_default();
+        let unchanged = previous.zip(self.instances.get(self.selected))
+            .is_some_and(|((_, before), (_, after))| before.log == after.log);
+        if !unchanged {
+            self.load();
+        }
+    }
+
+    pub fn instance(&self) -> Option<&(String, Instance)> {
+        self.instances.get(self.selected)
+    }
+
+    /// Unzips the selected instance's log and searches it again
+    fn load(&mut self) {
+        self.lines = match self.instance().map(|(_, a)| a.log.as_ref()) {
+            Some(Some(log)) => log.lines().map_err(|e| format!("{e:#}")),
+            _ => Ok(vec![]),
+        };
+        self.scroll = 0;
+        self.search();
+    }
+
+    /// Finds the lines containing the search, ignoring ascii case, and jumps to the first one
+    fn search(&mut self) {
+        let needle = self.search.value().to_ascii_lowercase();
+        self.matches = match (&self.lines, needle.is_empty()) {
+            (Ok(lines), false) => lines.iter()
+                .enumerate()
+                .filter(|(_, line)| line.to_ascii_lowercase().contains(&needle))
+                .map(|(index, _)| index)
+                .collect(),
+            _ => vec![],
+        };
+        self.current_match = None;
+        self.next_match();
+    }
+
+    /// Scrolls to the match after the current one, wrapping back to the first
+    fn next_match(&mut self) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let next = self.current_match.map(|a| (a + 1) % self.matches.len()).unwrap_or_default();
+        self.current_match = Some(next);
+        self.scroll = self.matches[next];
+    }
+
+    fn line_count(&self) -> usize {
+        self.lines.as_ref().map(|a| a.len()).unwrap_or_default()
+    }
+
+    pub fn handle_key(&mut self, key_event: KeyEvent) {
+        let count = self.instances.len().max(1);
+        let last_line = self.line_count().saturating_sub(1);
+        match key_event.code {
+            KeyCode::Left => {
+                self.selected = (self.selected + count - 1) % count;
+                self.load();
+            },
+            KeyCode::Right => {
+                self.selected = (self.selected + 1) % count;
+                self.load();
+            },
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down => self.scroll = (self.scroll + 1).min(last_line),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(PAGE),
+            KeyCode::PageDown => self.scroll = (self.scroll + PAGE).min(last_line),
+            KeyCode::Enter => self.next_match(),
+            _ => {
+                let before = self.search.value().to_owned();
+                self.search.handle_event(&Event::Key(key_event));
+                if self.search.value() != before {
+                    self.search();
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod logs_tests {
+    use crossterm::event::{KeyCode, KeyEvent};
+
+    use crate::source::fixtures;
+
+    use super::LogPaneState;
+
+    #[test]
+    fn search_cycles_matches_and_refresh_keeps_the_instance() {
+        let bots = fixtures::bots();
+        let loader = bots.iter().find(|a| a.id == "bot:customer_loader").unwrap();
+        let mut sync = bots.iter().find(|a| a.id == "bot:legacy_customer_sync").unwrap().clone();
+        let loader_instance = loader.instances.as_ref().unwrap()["0"].clone();
+        sync.instances.as_mut().unwrap().insert("1".to_string(), loader_instance.clone());
+
+        let mut pane = LogPaneState::new(&sync);
+        assert_eq!(pane.lines.as_ref().map(Vec::len), Ok(8));
+
+        pane.search = "ATTEMPT".into();
+        pane.search();
+        assert_eq!(pane.matches, vec![3, 4]);
+        assert_eq!(pane.scroll, 3);
+        pane.handle_key(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(pane.scroll, 4);
+        pane.handle_key(KeyEvent::from(KeyCode::Enter));
+        assert_eq!((pane.current_match, pane.scroll), (Some(0), 3));
+
+        pane.handle_key(KeyEvent::from(KeyCode::Right));
+        assert_eq!(pane.instance().map(|a| a.0.as_str()), Some("1"));
+        assert_eq!(pane.lines.as_ref().map(Vec::len), Ok(3));
+
+        // A new instance sorting ahead of the selected one doesn't move the selection
+        sync.instances.as_mut().unwrap().insert("05".to_string(), loader_instance);
+        pane.refresh(&sync);
+        assert_eq!(pane.selected, 2);
+        assert_eq!(pane.instance().map(|a| a.0.as_str()), Some("1"));
+        assert_eq!(pane.lines.as_ref().map(Vec::len), Ok(3));
+    }
+}
@@ -1,6 +1,13 @@
 pub mod bot;
 pub mod queue;
 pub mod bus_select;
+pub mod overview;
+pub mod topology;
+pub mod checkpoint;
+pub mod logs;
+pub mod inspector;
+pub mod events;
+pub mod entries;
 
 pub struct MainPage {}
 
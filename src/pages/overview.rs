@@ -0,0 +1,230 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use chrono::Utc;
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use tui_input::Input;
+
+use crate::{bot_stats::{merge_bot_stats, BotDynamoStatsRecord}, health::Health, lag::{worst_lags, ReadLag}, pages::{bot::BotSettings, queue::QueueRecord}};
+
+/// The columns of the overview table, in the order they are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverviewColumn {
+    #[default]
+    Name,
+    Health,
+    Paused,
+    LastInvoke,
+    ErrorCount,
+    Executions,
+    Errors,
+    Units,
+    ReadLag,
+}
+
+impl OverviewColumn {
+    pub const ALL: [OverviewColumn; 9] = [
+        OverviewColumn::Name,
+        OverviewColumn::Health,
+        OverviewColumn::Paused,
+        OverviewColumn::LastInvoke,
+        OverviewColumn::ErrorCount,
+        OverviewColumn::Executions,
+        OverviewColumn::Errors,
+        OverviewColumn::Units,
+        OverviewColumn::ReadLag,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            OverviewColumn::Name => "BOT",
+            OverviewColumn::Health => "HEALTH",
+            OverviewColumn::Paused => "PAUSED",
+            OverviewColumn::LastInvoke => "LAST INVOKE",
+            OverviewColumn::ErrorCount => "ERROR COUNT",
+            OverviewColumn::Executions => "EXECUTIONS",
+            OverviewColumn::Errors => "ERRORS",
+            OverviewColumn::Units => "UNITS",
+            OverviewColumn::ReadLag => "READ LAG",
+        }
+    }
+
+    /// The column to the right, or the left when `back` is set, wrapping around
+    pub fn cycle(&self, back: bool) -> Self {
+        let len = Self::ALL.len();
+        let index = Self::ALL.iter().position(|a| a == self).unwrap_or_default();
+        let next = if back { index + len - 1 } else { index + 1 };
+        Self::ALL[next % len]
+    }
+}
+
+/// A bot's settings and loaded stats boiled down to a row of the overview
+#[derive(Debug, Clone, Default)]
+pub struct OverviewRow {
+    pub id: String,
+    pub name: String,
+    pub health: Option<Health>,
+    pub paused: bool,
+    pub last_invoke: Option<i64>,
+    pub error_count: u32,
+    pub executions: u64,
+    pub errors: u64,
+    pub units: u64,
+    /// The read that is furthest behind its queue
+    pub lag: Option<ReadLag>,
+}
+
+impl OverviewRow {
+    /// How far the worst read is behind, caught up reads count as no lag
+    pub fn lag_millis(&self) -> Option<i64> {
+        self.lag.as_ref().map(ReadLag::behind)
+    }
+
+    fn compare(&self, other: &Self, column: OverviewColumn) -> Ordering {
+        match column {
+            OverviewColumn::Name => self.name.cmp(&other.name),
+            OverviewColumn::Health => self.health.as_ref().map(|a| a.status).cmp(&other.health.as_ref().map(|a| a.status)),
+            OverviewColumn::Paused => self.paused.cmp(&other.paused),
+            OverviewColumn::LastInvoke => self.last_invoke.cmp(&other.last_invoke),
+            OverviewColumn::ErrorCount => self.error_count.cmp(&other.error_count),
+            OverviewColumn::Executions => self.executions.cmp(&other.executions),
+            OverviewColumn::Errors => self.errors.cmp(&other.errors),
+            OverviewColumn::Units => self.units.cmp(&other.units),
+            OverviewColumn::ReadLag => self.lag_millis().cmp(&other.lag_millis()),
+        }
+    }
+}
+
+/// Every bot on the bus in one sortable, filterable table
+#[derive(Debug, Default)]
+pub struct OverviewPageState {
+    pub rows: Vec<OverviewRow>,
+    /// Indexes into `rows` that match the filter, in sorted order
+    pub visible: Vec<usize>,
+    pub sort: OverviewColumn,
+    pub descending: bool,
+    pub filter: Input,
+    pub current_select_index: usize,
+}
+
+impl OverviewPageState {
+    /// Rebuilds the rows from a load, keeping the filter, sort and selected bot
+    pub fn apply_refresh(&mut self, bots: &[BotSettings], stats: &[BotDynamoStatsRecord], records: &[QueueRecord], health: &HashMap<String, Health>) {
+        let selected = self.selected().map(|a| a.id.clone());
+        self.rows = Self::rows(bots, stats, records, Utc::now().timestamp_millis());
+        for row in self.rows.iter_mut() {
+            row.health = health.get(&row.name).cloned();
+        }
+        self.update_visible();
+        if let Some(id) = selected {
+            self.select_id(&id);
+        }
+    }
+
+    fn rows(bots: &[BotSettings], stats: &[BotDynamoStatsRecord], records: &[QueueRecord], now: i64) -> Vec<OverviewRow> {
+        let mut stats_by_bot: HashMap<&str, Vec<BotDynamoStatsRecord>> = HashMap::new();
+        for record in stats {
+            stats_by_bot.entry(record.id.as_str()).or_default().push(record.clone());
+        }
+
+        let mut worst_lags = worst_lags(bots, stats, records, now);
+
+        bots.iter()
+            .map(|bot| {
+                let merged = merge_bot_stats(stats_by_bot.get(bot.id.as_str()).map(Vec::as_slice).unwrap_or_default());
+                OverviewRow {
+                    id: bot.id.clone(),
+                    name: bot.id.replace("bot:", ""),
+                    health: None,
+                    paused: bot.paused.unwrap_or_default(),
+                    last_invoke: bot.invoke_time,
+                    error_count: bot.error_count.unwrap_or_default(),
+                    executions: merged.execution_stats.completions,
+                    errors: merged.execution_stats.errors,
+                    units: merged.execution_stats.units,
+                    lag: worst_lags.remove(&bot.id),
+                }
+            })
+            .collect()
+    }
+
+    /// Reapplies the filter and sort, call after either of them or the rows change
+    pub fn update_visible(&mut self) {
+        let matcher = SkimMatcherV2::default();
+        let filter = self.filter.value();
+        let mut visible: Vec<usize> = self.rows.iter()
+            .enumerate()
+            .filter(|(_, row)| filter.is_empty() || matcher.fuzzy_match(&row.name, filter).is_some())
+            .map(|(index, _)| index)
+            .collect();
+
+        let (sort, descending) = (self.sort, self.descending);
+        visible.sort_by(|a, b| {
+            let (a, b) = (&self.rows[*a], &self.rows[*b]);
+            let order = a.compare(b, sort);
+            let order = if descending { order.reverse() } else { order };
+            // Keep rows that tie in a stable, readable order
+            order.then_with(|| a.name.cmp(&b.name))
+        });
+
+        self.visible = visible;
+        self.current_select_index = self.current_select_index.min(self.visible.len().saturating_sub(1));
+    }
+
+    /// Sorts by `column`, flipping the direction when it is already the sort column
+    pub fn sort_by(&mut self, column: OverviewColumn) {
+        if self.sort == column {
+            self.descending = !self.descending;
+        } else {
+            self.sort = column;
+            // Names read best A to Z, everything else is most interesting at the top
+            self.descending = column != OverviewColumn::Name;
+        }
+        self.update_visible();
+    }
+
+    pub fn visible_rows(&self) -> impl Iterator<Item = &OverviewRow> {
+        self.visible.iter().map(|a| &self.rows[*a])
+    }
+
+    pub fn selected(&self) -> Option<&OverviewRow> {
+        self.visible.get(self.current_select_index).map(|a| &self.rows[*a])
+    }
+
+    fn select_id(&mut self, id: &str) {
+        if let Some(index) = self.visible.iter().position(|a| self.rows[*a].id == id) {
+            self.current_select_index = index;
+        }
+    }
+}
+
+#[cfg(test)]
+mod overview_tests {
+    use std::collections::HashMap;
+
+    use crate::source::fixtures;
+
+    use super::{OverviewColumn, OverviewPageState};
+
+    #[test]
+    fn rows_are_sorted_and_filtered() {
+        let (bots, stats) = (fixtures::bots(), fixtures::stats());
+        let mut page = OverviewPageState::default();
+
+        page.apply_refresh(&bots, &stats, &[], &HashMap::new());
+        assert_eq!(page.visible.len(), bots.len());
+        let names: Vec<&str> = page.visible_rows().map(|a| a.name.as_str()).collect();
+        assert!(names.windows(2).all(|a| a[0] <= a[1]));
+
+        page.sort_by(OverviewColumn::Executions);
+        let executions: Vec<u64> = page.visible_rows().map(|a| a.executions).collect();
+        assert!(executions.windows(2).all(|a| a[0] >= a[1]));
+        page.sort_by(OverviewColumn::Executions);
+        let executions: Vec<u64> = page.visible_rows().map(|a| a.executions).collect();
+        assert!(executions.windows(2).all(|a| a[0] <= a[1]));
+
+        page.filter = "enrich".into();
+        page.update_visible();
+        let names: Vec<&str> = page.visible_rows().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["order_enricher"]);
+    }
+}
@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use color_eyre::eyre::{bail, Result};
+use crossterm::event::{KeyCode, KeyEvent};
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use itertools::Itertools;
+use ratatui::widgets::ScrollbarState;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tui_input::Input;
+
+use crate::{app::Navigate, bot_stats::{BotDynamoStatsRecord, QueueStats, StatsOrEmpty}, dynamo::StatsRange, event_id::EventId, lag::{read_lags, ReadLag}, pages::bot::BotSettings};
+
+/// Strips the `queue:` prefix leo puts on queue ids
+pub fn queue_name(queue_id: &str) -> String {
+    queue_id.replace("queue:", "")
+}
+
+/// A queue's record in the leo_event table, which knows its head even when nothing wrote to it in the loaded stats
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueueRecord {
+    /// The queue's id, usually without the `queue:` prefix
+    pub event: String,
+    /// The newest event id written to the queue
+    pub max_eid: Option<String>,
+    /// When the queue was last written to, epoch millis
+    pub timestamp: Option<i64>,
+    pub archive: Option<QueueArchive>,
+    /// The bot that owns the queue
+    pub owner: Option<String>,
+    /// Anything else configured on the queue
+    pub settings: Option<Value>,
+}
+
+/// How far the queue has been archived to s3
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueueArchive {
+    /// The last event id archived
+    pub end: Option<String>,
+    /// When the archive last ran, epoch millis
+    pub timestamp: Option<i64>,
+}
+
+/// The merged stats between a single bot and the queue being viewed
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueConnection {
+    pub bot: String,
+    pub stats: QueueStats,
+    /// How far behind the bot is, only set for readers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lag: Option<ReadLag>,
+}
+
+#[derive(Debug)]
+pub struct QueueViewState {
+    pub vertical_scroll_state: ScrollbarState,
+    pub vertical_scroll: usize,
+    pub name: String,
+    pub writers: Vec<QueueConnection>,
+    pub readers: Vec<QueueConnection>,
+    /// The queue's record in the event table, if it has one
+    pub record: Option<QueueRecord>,
+}
+
+impl QueueViewState {
+    /// Builds the view from the stats, using the bots' checkpoints and the queue records to work out how far behind each reader is
+    pub fn new(name: &str, stats: &[BotDynamoStatsRecord], bots: &[BotSettings], records: &[QueueRecord]) -> Self {
+        // Sort by time so the checkpoint kept by QueueStats::merge is the most recent one
+        let ordered: Vec<&BotDynamoStatsRecord> = stats.iter()
+            .sorted_by_key(|a| a.time)
+            .collect();
+        let lags: HashMap<String, ReadLag> = read_lags(bots, stats, records, Utc::now().timestamp_millis())
+            .into_iter()
+            .filter(|((_, queue), _)| queue == name)
+            .map(|((bot, _), lag)| (bot.replace("bot:", ""), lag))
+            .collect();
+
+        Self {
+            vertical_scroll_state: ScrollbarState::default(),
+            vertical_scroll: 0,
+            name: name.to_owned(),
+            writers: Self::connections(name, ordered.iter().map(|a| (a.id.as_str(), &a.current.write))),
+            readers: Self::with_lags(Self::connections(name, ordered.iter().map(|a| (a.id.as_str(), &a.current.read))), lags),
+            record: records.iter().find(|a| queue_name(&a.event) == name).cloned(),
+        }
+    }
+
+    /// Attaches each reader's lag, adding readers that have a checkpoint on the queue but no stats in the window
+    fn with_lags(mut readers: Vec<QueueConnection>, mut lags: HashMap<String, ReadLag>) -> Vec<QueueConnection> {
+        for reader in readers.iter_mut() {
+            reader.lag = lags.remove(&reader.bot);
+        }
+        readers.extend(lags.into_iter()
+            .sorted_by(|a, b| a.0.cmp(&b.0))
+            .map(|(bot, lag)| QueueConnection {
+                bot,
+                stats: QueueStats {
+                    checkpoint: lag.checkpoint.clone(),
+                    source_timestamp: lag.source_timestamp.unwrap_or_default(),
+                    ..QueueStats::default()
+                },
+                lag: Some(lag),
+            }));
+
+        readers
+    }
+
+    fn connections<'a>(name: &str, stats: impl Iterator<Item = (&'a str, &'a StatsOrEmpty)>) -> Vec<QueueConnection> {
+        let mut by_bot: HashMap<String, QueueStats> = HashMap::new();
+
+        for (bot_id, queue_stats) in stats {
+            if let StatsOrEmpty::NotEmpty(queue_stats) = queue_stats {
+                queue_stats.iter()
+                    .filter(|(queue_id, _)| queue_name(queue_id) == name)
+                    .for_each(|(_, stat)| {
+                        by_bot.entry(bot_id.replace("bot:", ""))
+                            .and_modify(|e| e.merge(stat))
+                            .or_insert(stat.clone());
+                    });
+            }
+        }
+
+        by_bot.into_iter()
+            .map(|(bot, stats)| QueueConnection { bot, stats, lag: None })
+            .sorted_by(|a, b| b.stats.units.cmp(&a.stats.units).then(a.bot.cmp(&b.bot)))
+            .collect()
+    }
+
+    /// Total number of events written to the queue by every bot
+    pub fn events_written(&self) -> u64 {
+        self.writers.iter().map(|a| a.stats.units).sum()
+    }
+
+    /// The reader furthest behind the head of the queue
+    pub fn slowest_reader(&self) -> Option<&QueueConnection> {
+        self.readers.iter()
+            .filter(|a| a.lag.as_ref().is_some_and(|lag| !lag.caught_up))
+            .max_by_key(|a| a.lag.as_ref().and_then(|lag| lag.event_lag))
+    }
+
+    /// The write with the newest timestamp, this is the closest thing we have to the head of the queue
+    pub fn latest_write(&self) -> Option<&QueueStats> {
+        self.writers.iter()
+            .map(|a| &a.stats)
+            .max_by_key(|a| a.timestamp)
+    }
+
+    /// The newest event id on the queue, from its record or the latest write in the stats, whichever is newer
+    pub fn head(&self) -> Option<String> {
+        let written = self.latest_write().and_then(|a| a.checkpoint.clone());
+        let recorded = self.record.as_ref().and_then(|a| a.max_eid.clone());
+        [written, recorded].into_iter()
+            .flatten()
+            .max_by_key(|a| EventId::parse(a))
+    }
+
+    /// When the queue was last written to, from its record or the latest write in the stats
+    pub fn last_written(&self) -> Option<i64> {
+        let written = self.latest_write().map(|a| a.timestamp).filter(|a| *a > 0);
+        written.max(self.record.as_ref().and_then(|a| a.timestamp))
+    }
+}
+
+impl Navigate for QueueViewState {
+    fn navigate(&mut self, key_event: KeyEvent) -> color_eyre::Result<()> {
+        match key_event.code {
+            KeyCode::Up => {
+                self.vertical_scroll = self.vertical_scroll.saturating_sub(1);
+                self.vertical_scroll_state = self.vertical_scroll_state.position(self.vertical_scroll);
+            }
+            KeyCode::Down => {
+                self.vertical_scroll = self.vertical_scroll.saturating_add(1);
+                self.vertical_scroll_state = self.vertical_scroll_state.position(self.vertical_scroll);
+            }
+            _ => {},
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct QueuePageState {
+    pub queues: Vec<String>,
+    pub selected_queue_name: Option<String>,
+    pub current_select_index: usize,
+    pub selected_queue: Option<QueueViewState>,
+    pub search: Input,
+    pub search_results: Vec<String>,
+    /// The range the queue view is showing
+    pub range: StatsRange,
+    /// Every bot's stats over `range` when it isn't the range that was loaded
+    pub range_stats: Option<Vec<BotDynamoStatsRecord>>,
+    /// Every bot's settings from the last load, for their read checkpoints
+    pub bots: Vec<BotSettings>,
+    /// Every queue's record in the event table from the last load
+    pub records: Vec<QueueRecord>,
+}
+
+impl QueuePageState {
+
+    /// Collects the name of every queue that shows up in the read or write stats,
+    /// along with the idle ones that only have a record in the event table
+    pub fn queue_names(&mut self, stats: &[BotDynamoStatsRecord]) {
+        self.queues = stats.iter()
+            .flat_map(|a| [&a.current.read, &a.current.write])
+            .filter_map(|a| match a {
+                StatsOrEmpty::NotEmpty(queue_stats) => Some(queue_stats.keys()),
+                StatsOrEmpty::Empty {} => None,
+            })
+            .flatten()
+            .chain(self.records.iter().map(|a| &a.event))
+            .map(|a| queue_name(a))
+            .unique()
+            .sorted()
+            .collect()
+    }
+
+    pub fn search_queues(&mut self) {
+        let value = self.search.value();
+
+        // With nothing typed in show every queue
+        if value.is_empty() {
+            self.search_results = self.queues.clone();
+            return;
+        }
+
+        let mut matches = vec![];
+        let matcher = SkimMatcherV2::default();
+
+        // fuzzy match on the queue names
+        for name in &self.queues {
+            if let Some(match_score) = matcher.fuzzy_match(name, value) {
+                matches.push((name, match_score))
+            }
+        }
+
+        // Sort the matches by score descending
+        matches.sort_by_key(|a| std::cmp::Reverse(a.1));
+
+        self.search_results = matches.iter().map(|a| a.0.to_owned()).collect();
+    }
+
+    /// Rebuilds the queue list and the queue being viewed from a newer load
+    /// while keeping the current search, selection and scroll position
+    pub fn apply_refresh(&mut self, stats: &[BotDynamoStatsRecord], bots: &[BotSettings], records: &[QueueRecord]) {
+        self.bots = bots.to_vec();
+        self.records = records.to_vec();
+        self.queue_names(stats);
+        self.search_queues();
+        self.current_select_index = self.current_select_index.min(self.search_results.len().saturating_sub(1));
+
+        if self.range == StatsRange::LOAD {
+            self.rebuild_view(stats);
+        }
+    }
+
+    /// Whether the view is waiting on stats for a range other than the one loaded
+    pub fn needs_range_stats(&self) -> bool {
+        self.range != StatsRange::LOAD
+    }
+
+    /// The range being viewed, noting when its stats are still being fetched
+    pub fn range_label(&self) -> String {
+        if self.needs_range_stats() && self.range_stats.is_none() {
+            format!("loading {}...", self.range)
+        } else {
+            self.range.to_string()
+        }
+    }
+
+    /// Switches the view to another range, going back to the loaded stats when it's the range they were loaded for
+    pub fn set_range(&mut self, range: StatsRange, loaded: &[BotDynamoStatsRecord]) {
+        self.range = range;
+        self.range_stats = None;
+
+        if !self.needs_range_stats() {
+            self.rebuild_view(loaded);
+        }
+    }
+
+    /// Shows stats fetched for a range, as long as it's still the range being viewed
+    pub fn apply_range_stats(&mut self, range: StatsRange, stats: Vec<BotDynamoStatsRecord>) {
+        if range == self.range {
+            self.rebuild_view(&stats);
+            self.range_stats = Some(stats);
+        }
+    }
+
+    fn rebuild_view(&mut self, stats: &[BotDynamoStatsRecord]) {
+        if let (Some(view), Some(selected)) = (self.selected_queue.as_mut(), self.selected_queue_name.as_ref()) {
+            let mut refreshed = QueueViewState::new(selected, stats, &self.bots, &self.records);
+            refreshed.vertical_scroll = view.vertical_scroll;
+            refreshed.vertical_scroll_state = view.vertical_scroll_state;
+            *view = refreshed;
+        }
+    }
+
+    /// Opens the selected queue using the stats for the current range, `loaded` unless another range has been fetched
+    pub fn get_queue_details(&mut self, loaded: &[BotDynamoStatsRecord]) -> Result<()> {
+        let stats = match self.range_stats.as_deref() {
+            Some(range_stats) if self.needs_range_stats() => range_stats,
+            _ => loaded,
+        };
+
+        match self.selected_queue_name.as_ref() {
+            Some(selected) => {
+                if !self.queues.contains(selected) {
+                    bail!("unable to locate stats for selected queue '{selected}'")
+                }
+                self.selected_queue = Some(QueueViewState::new(selected, stats, &self.bots, &self.records));
+            },
+            None => bail!("no queue selected when attempting to get queue details"),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod queue_tests {
+    use crate::bot_stats::BotDynamoStatsRecord;
+
+    use super::{QueuePageState, QueueViewState};
+
+    fn record(bot: &str, time: i64, read: &str, write: &str) -> BotDynamoStatsRecord {
+        serde_json::from_str(&format!(r#"{{
+            "id": "bot:{bot}",
+            "bucket": "minute_15_{time}",
+            "period": "minute_15",
+            "time": {time},
+            "current": {{
+                "execution": null,
+                "read": {read},
+                "write": {write}
+            }}
+        }}"#)).unwrap()
+    }
+
+    #[test]
+    fn queue_names_are_collected_from_read_and_write() {
+        let stats = vec![
+            record("a", 1, r#"{"queue:in": {"checkpoint": "z/1", "source_timestamp": 1, "timestamp": 1, "units": 2}}"#, r#"{"queue:out": {"checkpoint": "z/2", "source_timestamp": 1, "timestamp": 1, "units": 2}}"#),
+            record("b", 1, r#"{"queue:out": {"checkpoint": "z/2", "source_timestamp": 1, "timestamp": 1, "units": 2}}"#, "{}"),
+        ];
+
+        let mut page = QueuePageState::default();
+        page.queue_names(&stats);
+
+        assert_eq!(page.queues, vec!["in".to_string(), "out".to_string()]);
+    }
+
+    #[test]
+    fn queue_view_merges_per_bot() {
+        let stats = vec![
+            record("writer", 2, "{}", r#"{"queue:out": {"checkpoint": "z/2", "source_timestamp": 20, "timestamp": 21, "units": 3}}"#),
+            record("writer", 1, "{}", r#"{"queue:out": {"checkpoint": "z/1", "source_timestamp": 10, "timestamp": 11, "units": 2}}"#),
+            record("reader", 2, r#"{"queue:out": {"checkpoint": "z/2", "source_timestamp": 20, "timestamp": 22, "units": 4}}"#, "{}"),
+        ];
+
+        let view = QueueViewState::new("out", &stats, &[], &[]);
+
+        assert_eq!(view.writers.len(), 1);
+        assert_eq!(view.readers.len(), 1);
+        assert_eq!(view.events_written(), 5);
+        assert_eq!(view.readers[0].bot, "reader");
+
+        let latest = view.latest_write().unwrap();
+        assert_eq!(latest.checkpoint.as_deref(), Some("z/2"));
+        assert_eq!(latest.source_timestamp, 20);
+    }
+}
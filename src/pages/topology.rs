@@ -0,0 +1,181 @@
+use crossterm::event::{KeyCode, KeyEvent};
+
+use crate::{app::Navigate, topology::{Node, Topology}};
+
+/// Which side of the focused node is being browsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Side {
+    #[default]
+    Upstream,
+    Downstream,
+}
+
+/// A row in one of the neighbor trees
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeItem {
+    /// Hops from the focused node, starting at 1
+    pub depth: usize,
+    pub node: Node,
+    /// Events moved over the edge to the row's parent
+    pub units: u64,
+}
+
+#[derive(Debug)]
+pub struct TopologyState {
+    pub topology: Topology,
+    pub focus: Node,
+    /// Nodes focused before this one, most recent last
+    pub trail: Vec<Node>,
+    pub upstream: Vec<TreeItem>,
+    pub downstream: Vec<TreeItem>,
+    pub side: Side,
+    pub selected: usize,
+}
+
+impl TopologyState {
+    /// How many hops out from the focused node the trees go
+    pub const DEPTH: usize = 3;
+
+    pub fn new(topology: Topology, focus: Node) -> Self {
+        let mut state = Self {
+            topology,
+            focus,
+            trail: vec![],
+            upstream: vec![],
+            downstream: vec![],
+            side: Side::default(),
+            selected: 0,
+        };
+        state.build_trees();
+        state
+    }
+
+    /// Swaps in a graph built from a newer load, keeping the focus and selection
+    pub fn refresh(&mut self, topology: Topology) {
+        self.topology = topology;
+        self.build_trees();
+    }
+
+    fn build_trees(&mut self) {
+        self.upstream = self.tree(Side::Upstream);
+        self.downstream = self.tree(Side::Downstream);
+        // Land on whichever side has something to browse
+        if self.items().is_empty() {
+            self.side = match self.side {
+                Side::Upstream => Side::Downstream,
+                Side::Downstream => Side::Upstream,
+            };
+        }
+        self.selected = self.selected.min(self.items().len().saturating_sub(1));
+    }
+
+    /// Walks out from the focus depth first, stopping at `DEPTH` hops or a node already on the path
+    fn tree(&self, side: Side) -> Vec<TreeItem> {
+        let mut items = vec![];
+        let mut path = vec![self.focus.clone()];
+        self.walk(side, &mut path, &mut items);
+        items
+    }
+
+    fn walk(&self, side: Side, path: &mut Vec<Node>, items: &mut Vec<TreeItem>) {
+        let Some(node) = path.last().cloned() else {
+            return;
+        };
+        let neighbors = match side {
+            Side::Upstream => self.topology.upstream(&node),
+            Side::Downstream => self.topology.downstream(&node),
+        };
+        for (neighbor, units) in neighbors {
+            if path.contains(neighbor) {
+                continue;
+            }
+            items.push(TreeItem { depth: path.len(), node: neighbor.clone(), units });
+            if path.len() < Self::DEPTH {
+                path.push(neighbor.clone());
+                self.walk(side, path, items);
+                path.pop();
+            }
+        }
+    }
+
+    /// The rows on the side being browsed
+    pub fn items(&self) -> &[TreeItem] {
+        match self.side {
+            Side::Upstream => &self.upstream,
+            Side::Downstream => &self.downstream,
+        }
+    }
+
+    /// Makes `node` the center of the view, remembering the current focus
+    pub fn focus_on(&mut self, node: Node) {
+        let previous = std::mem::replace(&mut self.focus, node);
+        self.trail.push(previous);
+        self.selected = 0;
+        self.build_trees();
+    }
+
+    /// Goes back to the previously focused node
+    pub fn back(&mut self) {
+        if let Some(node) = self.trail.pop() {
+            self.focus = node;
+            self.selected = 0;
+            self.build_trees();
+        }
+    }
+}
+
+impl Navigate for TopologyState {
+    fn navigate(&mut self, key_event: KeyEvent) -> color_eyre::Result<()> {
+        let len = self.items().len();
+        match key_event.code {
+            KeyCode::Down if len > 0 => self.selected = (self.selected + 1) % len,
+            KeyCode::Up if len > 0 => self.selected = (self.selected + len - 1) % len,
+            KeyCode::Left => {
+                self.side = Side::Upstream;
+                self.selected = self.selected.min(self.items().len().saturating_sub(1));
+            }
+            KeyCode::Right => {
+                self.side = Side::Downstream;
+                self.selected = self.selected.min(self.items().len().saturating_sub(1));
+            }
+            KeyCode::Enter => {
+                if let Some(item) = self.items().get(self.selected).cloned() {
+                    self.focus_on(item.node);
+                }
+            }
+            KeyCode::Backspace => self.back(),
+            _ => {},
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod topology_page_tests {
+    use crate::topology::{Node, Topology};
+
+    use super::{Side, TopologyState};
+
+    #[test]
+    fn trees_walk_out_from_the_focus_without_looping() {
+        let bot = |a: &str| Node::Bot(a.to_string());
+        let queue = |a: &str| Node::Queue(a.to_string());
+        let mut topology = Topology::default();
+        topology.edges.insert((bot("loader"), queue("orders")), 10);
+        topology.edges.insert((queue("orders"), bot("enricher")), 8);
+        topology.edges.insert((bot("enricher"), queue("orders")), 1);
+        topology.edges.insert((bot("enricher"), queue("enriched")), 7);
+
+        let mut state = TopologyState::new(topology, queue("orders"));
+        let upstream: Vec<(usize, &str)> = state.upstream.iter().map(|a| (a.depth, a.node.name())).collect();
+        assert_eq!(upstream, vec![(1, "enricher"), (1, "loader")]);
+        let downstream: Vec<(usize, &str)> = state.downstream.iter().map(|a| (a.depth, a.node.name())).collect();
+        assert_eq!(downstream, vec![(1, "enricher"), (2, "enriched")]);
+
+        state.side = Side::Downstream;
+        state.focus_on(queue("enriched"));
+        assert_eq!(state.side, Side::Upstream);
+        state.back();
+        assert_eq!(state.focus, queue("orders"));
+    }
+}
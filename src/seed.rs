@@ -0,0 +1,173 @@
+use aws_sdk_dynamodb::{config::http::HttpResponse, error::SdkError, operation::create_table::{CreateTableError, CreateTableOutput}, types::{AttributeDefinition, BillingMode, GlobalSecondaryIndex, KeySchemaElement, KeyType, Projection, ProjectionType, ScalarAttributeType}, Client};
+use chrono::Utc;
+use color_eyre::eyre::{bail, Context};
+use serde_dynamo::to_item;
+
+use crate::{bot_stats::BotDynamoStatsRecord, cli::SeedCommand, leo_config::LeoConfig, pages::{bot::BotSettings, entries::TableEntry, queue::QueueRecord}, source::{read_fixture, shift_times, shift_times_to_now}, stream::StreamEvent};
+
+/// How many items were written to each table by [`seed`]
+#[derive(Debug, Default)]
+pub struct SeedSummary {
+    pub bots: usize,
+    pub stats: usize,
+    pub queues: usize,
+    pub events: usize,
+    pub systems: usize,
+    pub settings: usize,
+}
+
+/// Creates the cron, stats, event, stream, system and settings tables for the bus and loads the fixture files named in
+/// `files` into them. Every time and event id is moved forward by whole hours so the newest stats land in the current hour,
+/// unless `keep_times` is set
+pub async fn seed(client: &Client, config: &LeoConfig, files: &SeedCommand) -> color_eyre::Result<SeedSummary> {
+    if config.endpoint_url.is_none() {
+        bail!("seed only runs against a custom endpoint, set --endpoint-url or EndpointUrl for the bus");
+    }
+
+    create_cron_table(client, &config.leo_cron).await?;
+    create_stats_table(client, &config.leo_stats).await?;
+    create_event_table(client, &config.leo_event).await?;
+    create_stream_table(client, &config.leo_stream).await?;
+    create_cron_table(client, &config.leo_system).await?;
+    create_cron_table(client, &config.leo_settings).await?;
+
+    let mut bots = read_fixture::<BotSettings>(&files.bots)?;
+    let mut stats = read_fixture::<BotDynamoStatsRecord>(&files.stats)?;
+    let mut queues = read_fixture::<QueueRecord>(&files.queues)?;
+    let mut events = read_fixture::<StreamEvent>(&files.events)?;
+    let systems = read_fixture::<TableEntry>(&files.systems)?;
+    let settings = read_fixture::<TableEntry>(&files.settings)?;
+
+    if !files.keep_times {
+        let shift = shift_times_to_now(&mut bots, &mut stats, Utc::now())?;
+        queues.iter_mut().chain(events.iter_mut()).for_each(|a| shift_times(a, shift));
+    }
+
+    for bot in &bots {
+        client.put_item()
+            .table_name(&config.leo_cron)
+            .set_item(Some(to_item(bot)?))
+            .send().await.wrap_err_with(|| format!("failed to write bot|{}", config.leo_cron))?;
+    }
+    for stat in &stats {
+        client.put_item()
+            .table_name(&config.leo_stats)
+            .set_item(Some(to_item(stat)?))
+            .send().await.wrap_err_with(|| format!("failed to write stats|{}", config.leo_stats))?;
+    }
+    for queue in &queues {
+        client.put_item()
+            .table_name(&config.leo_event)
+            .set_item(Some(to_item(queue)?))
+            .send().await.wrap_err_with(|| format!("failed to write queue|{}", config.leo_event))?;
+    }
+    for event in &events {
+        client.put_item()
+            .table_name(&config.leo_stream)
+            .set_item(Some(to_item(event)?))
+            .send().await.wrap_err_with(|| format!("failed to write event|{}", config.leo_stream))?;
+    }
+    for system in &systems {
+        client.put_item()
+            .table_name(&config.leo_system)
+            .set_item(Some(to_item(system)?))
+            .send().await.wrap_err_with(|| format!("failed to write system|{}", config.leo_system))?;
+    }
+    for setting in &settings {
+        client.put_item()
+            .table_name(&config.leo_settings)
+            .set_item(Some(to_item(setting)?))
+            .send().await.wrap_err_with(|| format!("failed to write setting|{}", config.leo_settings))?;
+    }
+
+    Ok(SeedSummary {
+        bots: bots.len(),
+        stats: stats.len(),
+        queues: queues.len(),
+        events: events.len(),
+        systems: systems.len(),
+        settings: settings.len(),
+    })
+}
+
+/// The cron, system and settings tables are all keyed by id alone
+async fn create_cron_table(client: &Client, table_name: &str) -> color_eyre::Result<()> {
+    let result = client.create_table()
+        .table_name(table_name)
+        .attribute_definitions(attribute("id", ScalarAttributeType::S)?)
+        .key_schema(key("id", KeyType::Hash)?)
+        .billing_mode(BillingMode::PayPerRequest)
+        .send().await;
+
+    ignore_existing(table_name, result)
+}
+
+async fn create_stats_table(client: &Client, table_name: &str) -> color_eyre::Result<()> {
+    let period_time_index = GlobalSecondaryIndex::builder()
+        .index_name("period-time-index")
+        .key_schema(key("period", KeyType::Hash)?)
+        .key_schema(key("time", KeyType::Range)?)
+        .projection(Projection::builder().projection_type(ProjectionType::All).build())
+        .build()?;
+
+    let result = client.create_table()
+        .table_name(table_name)
+        .attribute_definitions(attribute("id", ScalarAttributeType::S)?)
+        .attribute_definitions(attribute("bucket", ScalarAttributeType::S)?)
+        .attribute_definitions(attribute("period", ScalarAttributeType::S)?)
+        .attribute_definitions(attribute("time", ScalarAttributeType::N)?)
+        .key_schema(key("id", KeyType::Hash)?)
+        .key_schema(key("bucket", KeyType::Range)?)
+        .global_secondary_indexes(period_time_index)
+        .billing_mode(BillingMode::PayPerRequest)
+        .send().await;
+
+    ignore_existing(table_name, result)
+}
+
+async fn create_event_table(client: &Client, table_name: &str) -> color_eyre::Result<()> {
+    let result = client.create_table()
+        .table_name(table_name)
+        .attribute_definitions(attribute("event", ScalarAttributeType::S)?)
+        .key_schema(key("event", KeyType::Hash)?)
+        .billing_mode(BillingMode::PayPerRequest)
+        .send().await;
+
+    ignore_existing(table_name, result)
+}
+
+async fn create_stream_table(client: &Client, table_name: &str) -> color_eyre::Result<()> {
+    let result = client.create_table()
+        .table_name(table_name)
+        .attribute_definitions(attribute("event", ScalarAttributeType::S)?)
+        .attribute_definitions(attribute("eid", ScalarAttributeType::S)?)
+        .key_schema(key("event", KeyType::Hash)?)
+        .key_schema(key("eid", KeyType::Range)?)
+        .billing_mode(BillingMode::PayPerRequest)
+        .send().await;
+
+    ignore_existing(table_name, result)
+}
+
+fn attribute(name: &str, attribute_type: ScalarAttributeType) -> color_eyre::Result<AttributeDefinition> {
+    Ok(AttributeDefinition::builder()
+        .attribute_name(name)
+        .attribute_type(attribute_type)
+        .build()?)
+}
+
+fn key(name: &str, key_type: KeyType) -> color_eyre::Result<KeySchemaElement> {
+    Ok(KeySchemaElement::builder()
+        .attribute_name(name)
+        .key_type(key_type)
+        .build()?)
+}
+
+/// Seeding twice should just reload the data, so a table that already exists isn't an error
+fn ignore_existing(table_name: &str, result: Result<CreateTableOutput, SdkError<CreateTableError, HttpResponse>>) -> color_eyre::Result<()> {
+    match result {
+        Ok(_) => Ok(()),
+        Err(e) if e.as_service_error().is_some_and(|a| a.is_resource_in_use_exception()) => Ok(()),
+        Err(e) => Err(e).wrap_err_with(|| format!("failed to create table {table_name}")),
+    }
+}
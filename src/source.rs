@@ -0,0 +1,421 @@
+use std::{fmt::Debug, fs::read_to_string, path::{Path, PathBuf}, str::FromStr};
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::Client;
+use chrono::{DateTime, Duration, Utc};
+use color_eyre::eyre::{bail, Context};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::{actions::PendingAction, bot_stats::BotDynamoStatsRecord, dynamo::{get_all_bot_details, get_all_bot_stats_for_period, get_all_queue_records, get_bot_stats_from_time, get_stream_events, get_table_entries, update_bot_action, AllBuckets, BotBucket, Period, QuerySummary}, event_id::EventId, leo_config::LeoConfig, pages::{bot::BotSettings, entries::{EntryTable, TableEntry}, queue::QueueRecord}, stream::{page_of, StreamEvent, StreamPosition}};
+
+/// Called with the running totals after each page of a query
+pub type OnProgress<'a> = &'a mut (dyn FnMut(&QuerySummary) + Send);
+
+/// Where the app and headless commands read bots and stats from
+#[async_trait]
+pub trait BotmonSource: Debug + Send + Sync {
+    /// The table or file bot settings come from, shown while loading
+    fn bots_location(&self) -> String;
+
+    /// The table or file stats come from, shown while loading
+    fn stats_location(&self) -> String;
+
+    /// The table or file queue records come from, shown while loading
+    fn queues_location(&self) -> String;
+
+    /// Every bot's settings
+    async fn bot_settings(&self, on_progress: OnProgress<'_>) -> color_eyre::Result<(Vec<BotSettings>, QuerySummary)>;
+
+    /// Every queue's record from the event table
+    async fn queue_records(&self, on_progress: OnProgress<'_>) -> color_eyre::Result<(Vec<QueueRecord>, QuerySummary)>;
+
+    /// Every bot's stats for the bucket's period and time range
+    async fn stats_for_period(&self, bucket: AllBuckets, on_progress: OnProgress<'_>) -> color_eyre::Result<(Vec<BotDynamoStatsRecord>, QuerySummary)>;
+
+    /// A single bot's stats for the bucket's period and time range
+    async fn stats_for_bot(&self, bot_id: &str, bucket: AllBuckets) -> color_eyre::Result<(Vec<BotDynamoStatsRecord>, QuerySummary)>;
+
+    /// Writes a confirmed action to the bot's settings, failing when they changed since they were loaded
+    async fn apply_action(&self, pending: &PendingAction, now: i64) -> color_eyre::Result<()>;
+
+    /// A page of the events on a queue from `position`, oldest first
+    async fn stream_events(&self, queue: &str, position: &StreamPosition, limit: usize) -> color_eyre::Result<(Vec<StreamEvent>, QuerySummary)>;
+
+    /// Every entry in the system or settings table
+    async fn entries(&self, table: EntryTable) -> color_eyre::Result<(Vec<TableEntry>, QuerySummary)>;
+}
+
+/// Reads from the bus's DynamoDB tables
+#[derive(Debug)]
+pub struct DynamoSource {
+    client: Client,
+    config: LeoConfig,
+}
+
+impl DynamoSource {
+    /// Builds a client with the bus's region, profile, role and endpoint
+    pub async fn connect(config: LeoConfig) -> Self {
+        let client = Client::new(&config.sdk_config().await);
+        Self {
+            client,
+            config,
+        }
+    }
+}
+
+#[async_trait]
+impl BotmonSource for DynamoSource {
+    fn bots_location(&self) -> String {
+        self.config.leo_cron.clone()
+    }
+
+    fn stats_location(&self) -> String {
+        self.config.leo_stats.clone()
+    }
+
+    fn queues_location(&self) -> String {
+        self.config.leo_event.clone()
+    }
+
+    async fn bot_settings(&self, on_progress: OnProgress<'_>) -> color_eyre::Result<(Vec<BotSettings>, QuerySummary)> {
+        get_all_bot_details(&self.client, &self.config.leo_cron, on_progress).await
+    }
+
+    async fn queue_records(&self, on_progress: OnProgress<'_>) -> color_eyre::Result<(Vec<QueueRecord>, QuerySummary)> {
+        get_all_queue_records(&self.client, &self.config.leo_event, on_progress).await
+    }
+
+    async fn stats_for_period(&self, bucket: AllBuckets, on_progress: OnProgress<'_>) -> color_eyre::Result<(Vec<BotDynamoStatsRecord>, QuerySummary)> {
+        get_all_bot_stats_for_period(&self.client, &self.config.leo_stats, bucket, on_progress).await
+    }
+
+    async fn stats_for_bot(&self, bot_id: &str, bucket: AllBuckets) -> color_eyre::Result<(Vec<BotDynamoStatsRecord>, QuerySummary)> {
+        get_bot_stats_from_time(&self.client, &self.config.leo_stats, bot_id, bucket, |_| {}).await
+    }
+
+    async fn apply_action(&self, pending: &PendingAction, now: i64) -> color_eyre::Result<()> {
+        update_bot_action(&self.client, &self.config.leo_cron, pending, now).await
+    }
+
+    async fn stream_events(&self, queue: &str, position: &StreamPosition, limit: usize) -> color_eyre::Result<(Vec<StreamEvent>, QuerySummary)> {
+        get_stream_events(&self.client, &self.config.leo_stream, queue, position, limit).await
+    }
+
+    async fn entries(&self, table: EntryTable) -> color_eyre::Result<(Vec<TableEntry>, QuerySummary)> {
+        let table_name = match table {
+            EntryTable::System => &self.config.leo_system,
+            EntryTable::Settings => &self.config.leo_settings,
+        };
+        get_table_entries(&self.client, table_name).await
+    }
+}
+
+/// Reads json dumps of the cron, stats, event, stream, system and settings tables, `bot_settings.json`, `bot_stats.json`
+/// and an optional `leo_event.json`, `leo_stream.json`, `leo_system.json` and `leo_settings.json` in a directory. Everything is loaded up front so demos and tests never touch aws
+#[derive(Debug)]
+pub struct FileSource {
+    bots_path: PathBuf,
+    stats_path: PathBuf,
+    queues_path: PathBuf,
+    stream_path: PathBuf,
+    systems_path: PathBuf,
+    settings_path: PathBuf,
+    bots: Vec<BotSettings>,
+    stats: Vec<BotDynamoStatsRecord>,
+    queues: Vec<QueueRecord>,
+    /// Every event, with batched items unpacked
+    events: Vec<StreamEvent>,
+    systems: Vec<TableEntry>,
+    settings: Vec<TableEntry>,
+}
+
+impl FileSource {
+    /// Loads the dumps in `dir`. With `shift_to_now` every time is moved forward so the newest stats are from the current hour
+    pub fn open(dir: impl AsRef<Path>, shift_to_now: bool) -> color_eyre::Result<Self> {
+        let bots_path = dir.as_ref().join("bot_settings.json");
+        let stats_path = dir.as_ref().join("bot_stats.json");
+        let queues_path = dir.as_ref().join("leo_event.json");
+        let stream_path = dir.as_ref().join("leo_stream.json");
+        let systems_path = dir.as_ref().join("leo_system.json");
+        let settings_path = dir.as_ref().join("leo_settings.json");
+
+        let mut bots = read_fixture::<BotSettings>(&bots_path)?;
+        let mut stats = read_fixture::<BotDynamoStatsRecord>(&stats_path)?;
+        let mut queues = match queues_path.exists() {
+            true => read_fixture::<QueueRecord>(&queues_path)?,
+            false => vec![],
+        };
+        let mut events = match stream_path.exists() {
+            true => read_fixture::<StreamEvent>(&stream_path)?,
+            false => vec![],
+        };
+        let systems = match systems_path.exists() {
+            true => read_fixture::<TableEntry>(&systems_path)?,
+            false => vec![],
+        };
+        let settings = match settings_path.exists() {
+            true => read_fixture::<TableEntry>(&settings_path)?,
+            false => vec![],
+        };
+        if shift_to_now {
+            let shift = shift_times_to_now(&mut bots, &mut stats, Utc::now())?;
+            queues.iter_mut().chain(events.iter_mut()).for_each(|a| shift_times(a, shift));
+        }
+
+        let mut unpacked = vec![];
+        for event in events {
+            unpacked.extend(serde_json::from_value::<StreamEvent>(event)?.unpack()?);
+        }
+
+        Ok(Self {
+            bots: bots.into_iter().map(serde_json::from_value).collect::<Result<_, _>>()?,
+            stats: stats.into_iter().map(serde_json::from_value).collect::<Result<_, _>>()?,
+            queues: queues.into_iter().map(serde_json::from_value).collect::<Result<_, _>>()?,
+            events: unpacked,
+            systems: systems.into_iter().map(serde_json::from_value).collect::<Result<_, _>>()?,
+            settings: settings.into_iter().map(serde_json::from_value).collect::<Result<_, _>>()?,
+            bots_path,
+            stats_path,
+            queues_path,
+            stream_path,
+            systems_path,
+            settings_path,
+        })
+    }
+
+    fn stats_in(&self, bucket: AllBuckets) -> impl Iterator<Item = &BotDynamoStatsRecord> {
+        let period = bucket.period.to_string();
+        self.stats.iter()
+            .filter(move |a| a.period == period && (bucket.start..=bucket.end).contains(&a.time))
+    }
+}
+
+#[async_trait]
+impl BotmonSource for FileSource {
+    fn bots_location(&self) -> String {
+        self.bots_path.display().to_string()
+    }
+
+    fn stats_location(&self) -> String {
+        self.stats_path.display().to_string()
+    }
+
+    fn queues_location(&self) -> String {
+        self.queues_path.display().to_string()
+    }
+
+    async fn bot_settings(&self, on_progress: OnProgress<'_>) -> color_eyre::Result<(Vec<BotSettings>, QuerySummary)> {
+        let summary = file_summary(self.bots.len());
+        on_progress(&summary);
+        Ok((self.bots.clone(), summary))
+    }
+
+    async fn queue_records(&self, on_progress: OnProgress<'_>) -> color_eyre::Result<(Vec<QueueRecord>, QuerySummary)> {
+        let summary = file_summary(self.queues.len());
+        on_progress(&summary);
+        Ok((self.queues.clone(), summary))
+    }
+
+    async fn stats_for_period(&self, bucket: AllBuckets, on_progress: OnProgress<'_>) -> color_eyre::Result<(Vec<BotDynamoStatsRecord>, QuerySummary)> {
+        let stats: Vec<BotDynamoStatsRecord> = self.stats_in(bucket).cloned().collect();
+        let summary = file_summary(stats.len());
+        on_progress(&summary);
+        Ok((stats, summary))
+    }
+
+    async fn stats_for_bot(&self, bot_id: &str, bucket: AllBuckets) -> color_eyre::Result<(Vec<BotDynamoStatsRecord>, QuerySummary)> {
+        let stats: Vec<BotDynamoStatsRecord> = self.stats_in(bucket)
+            .filter(|a| a.id == bot_id)
+            .cloned()
+            .collect();
+        let summary = file_summary(stats.len());
+        Ok((stats, summary))
+    }
+
+    async fn apply_action(&self, pending: &PendingAction, _now: i64) -> color_eyre::Result<()> {
+        bail!("can't {} {}, fixtures in {} are read only", pending.action, pending.bot.id, self.bots_path.display())
+    }
+
+    async fn stream_events(&self, queue: &str, position: &StreamPosition, limit: usize) -> color_eyre::Result<(Vec<StreamEvent>, QuerySummary)> {
+        if !self.stream_path.exists() {
+            bail!("there are no events to browse without {}", self.stream_path.display());
+        }
+        let events = page_of(&self.events, queue, position, limit);
+        let summary = file_summary(events.len());
+        Ok((events, summary))
+    }
+
+    async fn entries(&self, table: EntryTable) -> color_eyre::Result<(Vec<TableEntry>, QuerySummary)> {
+        let (path, entries) = match table {
+            EntryTable::System => (&self.systems_path, &self.systems),
+            EntryTable::Settings => (&self.settings_path, &self.settings),
+        };
+        if !path.exists() {
+            bail!("there are no {table} to browse without {}", path.display());
+        }
+        Ok((entries.clone(), file_summary(entries.len())))
+    }
+}
+
+/// A file is read in one go and costs nothing
+fn file_summary(items: usize) -> QuerySummary {
+    QuerySummary {
+        pages: 1,
+        items,
+        consumed_capacity: 0.0,
+    }
+}
+
+/// Reads a json array fixture, checking each entry has the shape of `T` while keeping every field
+pub fn read_fixture<T: DeserializeOwned>(path: impl AsRef<Path>) -> color_eyre::Result<Vec<Value>> {
+    let path = path.as_ref();
+    let raw = read_to_string(path).wrap_err_with(|| format!("failed to read fixture {}", path.display()))?;
+    let path = path.display();
+    let values: Vec<Value> = serde_json::from_str(&raw).wrap_err_with(|| format!("{path} is not a json array"))?;
+
+    for (index, value) in values.iter().enumerate() {
+        serde_json::from_value::<T>(value.clone()).wrap_err_with(|| format!("{path}[{index}] has the wrong shape"))?;
+    }
+
+    Ok(values)
+}
+
+/// The fixture dumps with their original 2024-07-15 times, shared by tests across the crate
+#[cfg(test)]
+pub mod fixtures {
+    use serde::de::DeserializeOwned;
+
+    use crate::{bot_stats::BotDynamoStatsRecord, pages::bot::BotSettings};
+
+    use super::read_fixture;
+
+    /// 2024-07-15 12:05, just after the newest stats
+    pub const NOW: i64 = 1721045100000;
+
+    /// Every entry in `fixtures/<name>`
+    pub fn load<T: DeserializeOwned>(name: &str) -> Vec<T> {
+        read_fixture::<T>(format!("./fixtures/{name}")).unwrap()
+            .into_iter()
+            .map(|a| serde_json::from_value(a).unwrap())
+            .collect()
+    }
+
+    pub fn bots() -> Vec<BotSettings> {
+        load("bot_settings.json")
+    }
+
+    /// Stats for every period
+    pub fn stats() -> Vec<BotDynamoStatsRecord> {
+        load("bot_stats.json")
+    }
+
+    /// Only the minute_15 stats, what a load reads
+    pub fn minute_stats() -> Vec<BotDynamoStatsRecord> {
+        stats().into_iter().filter(|a| a.period == "minute_15").collect()
+    }
+}
+
+/// Fields holding epoch millis in bot settings, stats, queue records and events
+const TIME_FIELDS: [&str; 11] = [
+    "time", "timestamp", "source_timestamp", "ended_timestamp", "event_source_timestamp", "invokeTime", "startTime", "completedTime", "trigger",
+    "scheduledTrigger", "token",
+];
+
+/// Moves the bots and stats forward by whole hours so the newest stats record falls in the hour of `now`,
+/// rebuilding the buckets to match. Returns the millis everything was moved by
+pub fn shift_times_to_now(bots: &mut [Value], stats: &mut [Value], now: DateTime<Utc>) -> color_eyre::Result<i64> {
+    let Some(newest) = stats.iter().filter_map(|a| a["time"].as_i64()).max() else {
+        return Ok(0);
+    };
+    let hours = (now.timestamp_millis() - newest) / Duration::hours(1).num_milliseconds();
+    let shift = Duration::hours(hours).num_milliseconds();
+
+    for bot in bots.iter_mut() {
+        shift_times(bot, shift);
+    }
+
+    for stat in stats {
+        shift_times(stat, shift);
+        let time = stat["time"].as_i64().unwrap_or_default();
+        let period = Period::from_str(stat["period"].as_str().unwrap_or_default()).map_err(color_eyre::eyre::Error::msg)?;
+        let Some(date) = DateTime::from_timestamp_millis(time) else {
+            bail!("stats time {time} is out of range");
+        };
+
+        stat["bucket"] = BotBucket::new(period, date).to_string().into();
+    }
+
+    Ok(shift)
+}
+
+/// Shifts every known time field and event id in `value` by `shift` millis
+pub fn shift_times(value: &mut Value, shift: i64) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                match field.as_i64() {
+                    Some(time) if TIME_FIELDS.contains(&key.as_str()) => *field = (time + shift).into(),
+                    _ => shift_times(field, shift),
+                }
+            }
+        },
+        Value::Array(values) => values.iter_mut().for_each(|a| shift_times(a, shift)),
+        Value::String(eid) => {
+            if let Some(shifted) = EventId::parse(eid).and_then(|a| EventId::from_timestamp(a.timestamp + shift, a.sequence)) {
+                *eid = shifted;
+            }
+        },
+        _ => {},
+    }
+}
+
+#[cfg(test)]
+mod source_tests {
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+
+    use crate::dynamo::{AllBuckets, Period};
+
+    use super::{shift_times_to_now, BotmonSource, FileSource};
+
+    #[test]
+    fn times_are_shifted_into_the_current_hour() {
+        // 2024-07-15 12:00 and 11:45
+        let mut stats = vec![
+            json!({"period": "minute_15", "time": 1721044800000_i64, "bucket": "minute_15_2024-07-15 12:00"}),
+            json!({"period": "minute_15", "time": 1721043900000_i64, "bucket": "minute_15_2024-07-15 11:45"}),
+        ];
+        let mut bots = vec![
+            json!({"invokeTime": 1721044680000_i64, "errorCount": 6, "checkpoints": {"read": {"queue:a": {"checkpoint": "z/2024/07/15/09/58/1721037500000-0000003"}}}}),
+        ];
+        let now = Utc.with_ymd_and_hms(2024, 7, 16, 9, 20, 0).unwrap();
+
+        shift_times_to_now(&mut bots, &mut stats, now).unwrap();
+
+        assert_eq!(stats[0]["bucket"], "minute_15_2024-07-16 09:00");
+        assert_eq!(stats[1]["bucket"], "minute_15_2024-07-16 08:45");
+        assert_eq!(stats[0]["time"], 1721120400000_i64);
+        assert_eq!(bots[0]["invokeTime"], 1721120280000_i64);
+        assert_eq!(bots[0]["errorCount"], 6);
+        assert_eq!(bots[0]["checkpoints"]["read"]["queue:a"]["checkpoint"], "z/2024/07/16/06/58/1721113100000-0000003");
+    }
+
+    #[tokio::test]
+    async fn file_source_filters_by_period_range_and_bot() {
+        let source = FileSource::open("fixtures", false).unwrap();
+        // 2024-07-15 11:00 to 12:00
+        let bucket = AllBuckets { period: Period::Minute15, start: 1721041200000, end: 1721044800000 };
+
+        let (bots, _) = source.bot_settings(&mut |_| {}).await.unwrap();
+        let (stats, summary) = source.stats_for_period(bucket, &mut |_| {}).await.unwrap();
+        let (bot_stats, _) = source.stats_for_bot("bot:order_loader", bucket).await.unwrap();
+
+        assert_eq!(bots.len(), 6);
+        assert_eq!(stats.len(), 5 * 5);
+        assert_eq!(summary.items, stats.len());
+        assert!(stats.iter().all(|a| a.period == "minute_15"));
+        assert_eq!(bot_stats.len(), 5);
+        assert!(bot_stats.iter().all(|a| a.id == "bot:order_loader"));
+    }
+}
@@ -0,0 +1,171 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{event_id::EventId, instance_log::GzippedLog, pages::{bot::BotSettings, checkpoint::CheckpointTarget, queue::queue_name}};
+
+/// How many events a page of the leo_stream table holds
+pub const PAGE_SIZE: usize = 25;
+
+/// An event on a queue as it's stored in the leo_stream table, keyed by queue and event id
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamEvent {
+    /// The queue's name, without the `queue:` prefix
+    pub event: String,
+    pub eid: String,
+    /// The bot that wrote it
+    pub id: Option<String>,
+    pub timestamp: Option<i64>,
+    pub event_source_timestamp: Option<i64>,
+    pub correlation_id: Option<Value>,
+    pub payload: Option<Value>,
+    /// Where the payload is when it was too big for the table
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub s3: Option<Value>,
+    /// Events the sdk batched into one item, newline delimited json
+    #[serde(skip_serializing)]
+    pub gzip: Option<GzippedLog>,
+}
+
+impl StreamEvent {
+    /// The events held by the item, unzipping any the sdk batched together. Batched events take the item's
+    /// queue, writer and times when they don't have their own, and its eid with their position as the sequence
+    pub fn unpack(self) -> color_eyre::Result<Vec<StreamEvent>> {
+        let Some(gzip) = self.gzip.as_ref() else {
+            return Ok(vec![self]);
+        };
+        let base = EventId::parse(&self.eid);
+        let item = serde_json::to_value(&self)?;
+
+        gzip.lines()?
+            .into_iter()
+            .filter(|a| !a.trim().is_empty())
+            .enumerate()
+            .map(|(index, line)| {
+                let mut value: Value = serde_json::from_str(&line)?;
+                if value.get("eid").and_then(|a| a.as_str()).is_none() {
+                    value["eid"] = base.and_then(|a| EventId::from_timestamp(a.timestamp, a.sequence + index as u32))
+                        .unwrap_or_else(|| format!("{}-{index}", self.eid))
+                        .into();
+                }
+                for field in ["event", "id", "timestamp", "event_source_timestamp"] {
+                    if value.get(field).is_none_or(Value::is_null) {
+                        value[field] = item[field].clone();
+                    }
+                }
+                Ok(serde_json::from_value(value)?)
+            })
+            .collect()
+    }
+}
+
+/// Where a page of events on a queue starts
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamPosition {
+    /// The oldest events still on the table
+    Start,
+    /// The newest events
+    End,
+    /// From an event id on, including it
+    At(String),
+    /// The events written after an event id
+    After(String),
+    /// The events written before an event id
+    Before(String),
+}
+
+impl StreamPosition {
+    /// Reads a position typed in as an event id, a time, epoch millis, `start`, `end`,
+    /// or `bot:<id>` for where that bot's read checkpoint on `queue` is
+    pub fn parse(input: &str, queue: &str, bots: &[BotSettings]) -> Result<Self, String> {
+        match input.trim() {
+            "" | "start" => Ok(StreamPosition::Start),
+            "end" => Ok(StreamPosition::End),
+            a if a.starts_with("bot:") => read_checkpoint(bots, a, queue).map(StreamPosition::At),
+            a => match a.parse::<CheckpointTarget>() {
+                Ok(CheckpointTarget::EventId(eid)) => Ok(StreamPosition::At(eid)),
+                Ok(CheckpointTarget::Time(time)) => EventId::before_timestamp(time)
+                    .map(StreamPosition::At)
+                    .ok_or_else(|| format!("{time} is out of range")),
+                Ok(CheckpointTarget::Head) => Ok(StreamPosition::End),
+                Ok(CheckpointTarget::Undo) | Err(_) => Err(format!("'{a}' isn't an event id, a time like 2024-07-15 12:30, epoch millis, start, end or bot:<id>")),
+            },
+        }
+    }
+}
+
+impl Display for StreamPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamPosition::Start => write!(f, "oldest"),
+            StreamPosition::End => write!(f, "newest"),
+            StreamPosition::At(eid) => write!(f, "from {eid}"),
+            StreamPosition::After(eid) => write!(f, "after {eid}"),
+            StreamPosition::Before(eid) => write!(f, "before {eid}"),
+        }
+    }
+}
+
+/// The read checkpoint `bot` has on `queue`
+pub fn read_checkpoint(bots: &[BotSettings], bot: &str, queue: &str) -> Result<String, String> {
+    let bot = bots.iter()
+        .find(|a| a.id == bot)
+        .ok_or_else(|| format!("unable to find {bot}"))?;
+    bot.checkpoints.as_ref()
+        .and_then(|a| a.read.as_ref())
+        .into_iter()
+        .flatten()
+        .find(|(key, _)| queue_name(key) == queue_name(queue))
+        .and_then(|(_, detail)| detail.checkpoint.as_ref())
+        .map(|a| a.to_string())
+        .ok_or_else(|| format!("{} has no read checkpoint on {}", bot.id, queue_name(queue)))
+}
+
+/// Picks out a page of `events`, sorted by event id, the way the table would be queried from `position`
+pub fn page_of(events: &[StreamEvent], queue: &str, position: &StreamPosition, limit: usize) -> Vec<StreamEvent> {
+    let queue = queue_name(queue);
+    let mut events: Vec<&StreamEvent> = events.iter().filter(|a| a.event == queue).collect();
+    events.sort_by(|a, b| a.eid.cmp(&b.eid));
+
+    let page: Vec<&StreamEvent> = match position {
+        StreamPosition::Start => events.into_iter().take(limit).collect(),
+        StreamPosition::At(eid) => events.into_iter().filter(|a| a.eid >= *eid).take(limit).collect(),
+        StreamPosition::After(eid) => events.into_iter().filter(|a| a.eid > *eid).take(limit).collect(),
+        StreamPosition::End | StreamPosition::Before(_) => {
+            let before: Vec<&StreamEvent> = match position {
+                StreamPosition::Before(eid) => events.into_iter().filter(|a| a.eid < *eid).collect(),
+                _ => events,
+            };
+            before[before.len().saturating_sub(limit)..].to_vec()
+        },
+    };
+
+    page.into_iter().cloned().collect()
+}
+
+#[cfg(test)]
+mod stream_tests {
+    use crate::source::fixtures;
+
+    use super::{page_of, StreamEvent, StreamPosition};
+
+    #[test]
+    fn positions_page_through_a_queue() {
+        let bots = fixtures::bots();
+        let events: Vec<StreamEvent> = fixtures::load::<StreamEvent>("leo_stream.json").into_iter().flat_map(|a| a.unpack().unwrap()).collect();
+
+        let checkpoint = StreamPosition::parse("bot:order_enricher", "orders", &bots).unwrap();
+        assert_eq!(checkpoint, StreamPosition::At("z/2024/07/15/11/59/1721044740000-0000003".to_string()));
+        let page = page_of(&events, "queue:orders", &checkpoint, 3);
+        assert_eq!(page[0].eid, "z/2024/07/15/11/59/1721044740000-0000003");
+        assert!(page.windows(2).all(|a| a[0].eid < a[1].eid));
+
+        let before = page_of(&events, "orders", &StreamPosition::Before(page[0].eid.clone()), 3);
+        assert_eq!(before.len(), 3);
+        assert!(before.iter().all(|a| a.eid < page[0].eid));
+
+        assert_eq!(StreamPosition::parse("2024-07-15 11:00", "orders", &bots), Ok(StreamPosition::At("z/2024/07/15/11/00/1721041200000".to_string())));
+        assert!(StreamPosition::parse("bot:order_loader", "orders", &bots).is_err());
+    }
+}
@@ -0,0 +1,241 @@
+use std::{collections::{BTreeMap, BTreeSet}, fmt::{Display, Write}, str::FromStr};
+
+use crate::{bot_stats::{BotDynamoStatsRecord, StatsOrEmpty}, pages::{bot::BotSettings, queue::queue_name}};
+
+/// A bot or queue in the bus's graph, by name without the `bot:`/`queue:` prefix
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Node {
+    Bot(String),
+    Queue(String),
+}
+
+impl Node {
+    pub fn name(&self) -> &str {
+        match self {
+            Node::Bot(name) | Node::Queue(name) => name,
+        }
+    }
+}
+
+impl Display for Node {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Node::Bot(name) => write!(f, "bot:{name}"),
+            Node::Queue(name) => write!(f, "queue:{name}"),
+        }
+    }
+}
+
+/// Who reads and writes what on a bus. Writes go from a bot to a queue and reads from a queue to a bot,
+/// each edge carrying the events moved over it in the stats it was built from
+#[derive(Debug, Clone, Default)]
+pub struct Topology {
+    pub edges: BTreeMap<(Node, Node), u64>,
+}
+
+impl Topology {
+    /// Builds the graph from the read and write stats, adding any connection only found in the bots' checkpoints with no events
+    pub fn build(bots: &[BotSettings], stats: &[BotDynamoStatsRecord]) -> Self {
+        let mut edges: BTreeMap<(Node, Node), u64> = BTreeMap::new();
+
+        for record in stats {
+            let bot = Node::Bot(record.id.replace("bot:", ""));
+            if let StatsOrEmpty::NotEmpty(reads) = &record.current.read {
+                for (queue, stat) in reads {
+                    *edges.entry((Node::Queue(queue_name(queue)), bot.clone())).or_default() += stat.units;
+                }
+            }
+            if let StatsOrEmpty::NotEmpty(writes) = &record.current.write {
+                for (queue, stat) in writes {
+                    *edges.entry((bot.clone(), Node::Queue(queue_name(queue)))).or_default() += stat.units;
+                }
+            }
+        }
+
+        for bot in bots {
+            let Some(checkpoints) = bot.checkpoints.as_ref() else {
+                continue;
+            };
+            let node = Node::Bot(bot.id.replace("bot:", ""));
+            for queue in checkpoints.read.iter().flat_map(|a| a.keys()) {
+                edges.entry((Node::Queue(queue_name(queue)), node.clone())).or_default();
+            }
+            for queue in checkpoints.write.iter().flat_map(|a| a.keys()) {
+                edges.entry((node.clone(), Node::Queue(queue_name(queue)))).or_default();
+            }
+        }
+
+        Self { edges }
+    }
+
+    /// The nodes feeding into `node` and the events each sent
+    pub fn upstream(&self, node: &Node) -> Vec<(&Node, u64)> {
+        self.edges.iter()
+            .filter(|((_, to), _)| to == node)
+            .map(|((from, _), units)| (from, *units))
+            .collect()
+    }
+
+    /// The nodes `node` feeds and the events each received
+    pub fn downstream(&self, node: &Node) -> Vec<(&Node, u64)> {
+        self.edges.iter()
+            .filter(|((from, _), _)| from == node)
+            .map(|((_, to), units)| (to, *units))
+            .collect()
+    }
+
+    pub fn contains(&self, node: &Node) -> bool {
+        self.edges.keys().any(|(from, to)| from == node || to == node)
+    }
+
+    /// Every bot and queue with an edge
+    pub fn nodes(&self) -> BTreeSet<&Node> {
+        self.edges.keys().flat_map(|(from, to)| [from, to]).collect()
+    }
+
+    /// The part of the graph within `hops` edges of `node`, following edges in either direction
+    pub fn around(&self, node: &Node, hops: usize) -> Topology {
+        let mut reached = BTreeSet::from([node.clone()]);
+        let mut frontier = vec![node.clone()];
+
+        for _ in 0..hops {
+            let mut next = vec![];
+            for current in frontier.iter() {
+                let neighbors = self.upstream(current).into_iter().chain(self.downstream(current));
+                for (neighbor, _) in neighbors {
+                    if reached.insert(neighbor.clone()) {
+                        next.push(neighbor.clone());
+                    }
+                }
+            }
+            frontier = next;
+        }
+
+        Topology {
+            edges: self.edges.iter()
+                .filter(|((from, to), _)| reached.contains(from) && reached.contains(to))
+                .map(|(edge, units)| (edge.clone(), *units))
+                .collect(),
+        }
+    }
+
+    /// Graphviz source with bots as boxes, queues as ellipses and `focus` in bold
+    pub fn to_dot(&self, focus: Option<&Node>) -> String {
+        let quote = |a: &str| format!("\"{}\"", a.replace('\\', "\\\\").replace('"', "\\\""));
+        let mut dot = String::from("digraph bus {\n    rankdir=LR;\n");
+
+        for node in self.nodes() {
+            let shape = match node {
+                Node::Bot(_) => "box",
+                Node::Queue(_) => "ellipse",
+            };
+            let style = if Some(node) == focus { ", style=bold" } else { "" };
+            let _ = writeln!(dot, "    {} [shape={shape}, label={}{style}];", quote(&node.to_string()), quote(node.name()));
+        }
+        for ((from, to), units) in self.edges.iter() {
+            let _ = writeln!(dot, "    {} -> {} [label=\"{units}\"];", quote(&from.to_string()), quote(&to.to_string()));
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// A mermaid flowchart with bots as rectangles, queues as stadiums and `focus` in bold
+    pub fn to_mermaid(&self, focus: Option<&Node>) -> String {
+        let ids: BTreeMap<&Node, String> = self.nodes().into_iter()
+            .enumerate()
+            .map(|(index, node)| (node, format!("n{index}")))
+            .collect();
+        let label = |a: &str| a.replace('"', "#quot;");
+        let mut mermaid = String::from("flowchart LR\n");
+
+        for (node, id) in ids.iter() {
+            let _ = match node {
+                Node::Bot(name) => writeln!(mermaid, "    {id}[\"{}\"]", label(name)),
+                Node::Queue(name) => writeln!(mermaid, "    {id}([\"{}\"])", label(name)),
+            };
+        }
+        for ((from, to), units) in self.edges.iter() {
+            let _ = writeln!(mermaid, "    {} -->|{units}| {}", ids[from], ids[to]);
+        }
+        if let Some(id) = focus.and_then(|a| ids.get(a)) {
+            let _ = writeln!(mermaid, "    style {id} stroke-width:3px");
+        }
+
+        mermaid
+    }
+}
+
+/// The graph languages topology can be exported as
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum GraphFormat {
+    #[default]
+    Dot,
+    Mermaid,
+}
+
+impl FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(GraphFormat::Dot),
+            "mermaid" => Ok(GraphFormat::Mermaid),
+            a => Err(format!("unknown graph format '{a}', expected dot or mermaid")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod topology_tests {
+    use crate::source::fixtures;
+
+    use super::{Node, Topology};
+
+    #[test]
+    fn edges_follow_reads_and_writes() {
+        let (bots, stats) = (fixtures::bots(), fixtures::minute_stats());
+
+        let topology = Topology::build(&bots, &stats);
+        let enricher = Node::Bot("order_enricher".to_string());
+
+        let upstream: Vec<&Node> = topology.upstream(&enricher).into_iter().map(|a| a.0).collect();
+        let downstream: Vec<&Node> = topology.downstream(&enricher).into_iter().map(|a| a.0).collect();
+        assert_eq!(upstream, vec![&Node::Queue("orders".to_string())]);
+        assert_eq!(downstream, vec![&Node::Queue("enriched_orders".to_string())]);
+        assert!(topology.downstream(&enricher)[0].1 > 0);
+        assert_eq!(topology.edges.len(), 6);
+    }
+
+    #[test]
+    fn exports_are_scoped_to_the_hops_around_a_node() {
+        let bot = |a: &str| Node::Bot(a.to_string());
+        let queue = |a: &str| Node::Queue(a.to_string());
+        let mut topology = Topology::default();
+        topology.edges.insert((bot("loader"), queue("orders")), 10);
+        topology.edges.insert((queue("orders"), bot("enricher")), 8);
+        topology.edges.insert((bot("enricher"), queue("enriched")), 7);
+        topology.edges.insert((queue("enriched"), bot("warehouse")), 6);
+
+        let around = topology.around(&bot("enricher"), 1);
+        assert_eq!(around.edges.len(), 2);
+
+        let dot = around.to_dot(Some(&bot("enricher")));
+        assert!(dot.starts_with("digraph bus {"));
+        assert!(dot.contains("\"bot:enricher\" [shape=box, label=\"enricher\", style=bold];"));
+        assert!(dot.contains("\"queue:orders\" -> \"bot:enricher\" [label=\"8\"];"));
+        assert!(!dot.contains("warehouse"));
+
+        let mermaid = around.to_mermaid(Some(&bot("enricher")));
+        assert_eq!(mermaid, [
+            "flowchart LR",
+            "    n0[\"enricher\"]",
+            "    n1([\"enriched\"])",
+            "    n2([\"orders\"])",
+            "    n0 -->|7| n1",
+            "    n2 -->|8| n0",
+            "    style n0 stroke-width:3px",
+            "",
+        ].join("\n"));
+    }
+}
@@ -0,0 +1,105 @@
+use ratatui::{layout::{Constraint, Direction, Layout, Rect}, style::{self, Color, Style, Stylize}, text::{Line, Span}, widgets::{Block, Borders, Cell, Paragraph, Row, Table}, Frame};
+
+use crate::alerts::{AlertState, AlertStatus};
+
+use style::palette::tailwind;
+use super::format_timestamp;
+
+pub fn alerts_ui(state: &mut AlertState, area: Rect, frame: &mut Frame) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(2)
+        .constraints(
+            [
+                Constraint::Length(5),
+                Constraint::Percentage(40),
+                Constraint::Percentage(60),
+            ]
+        )
+        .split(area);
+
+    let webhook = format!("Webhook: {}", state.config.webhook.as_deref().unwrap_or("-"));
+    let webhook = match state.notify_error.as_ref() {
+        Some(e) => Line::from(vec![
+            Span::raw(format!("{webhook} ")),
+            Span::styled(format!("last notification failed: {}", e.lines().next().unwrap_or_default()), Style::default().fg(Color::LightRed)),
+        ]),
+        None => Line::from(webhook),
+    };
+    let summary = vec![
+        Line::from(format!("Rules: {}", state.config.rules.len())),
+        webhook,
+        Line::from(format!("Desktop Notifications: {}", if state.config.desktop { "on" } else { "off" })),
+    ];
+    let paragraph = Paragraph::new(summary)
+        .block(Block::default().borders(Borders::ALL).title("Alerts"));
+    frame.render_widget(paragraph, chunks[0]);
+
+    let header_style = Style::default()
+        .fg(tailwind::SLATE.c200)
+        .bg(tailwind::BLUE.c900);
+    let widths = [
+        Constraint::Length(19),
+        Constraint::Length(10),
+        Constraint::Percentage(25),
+        Constraint::Percentage(20),
+        Constraint::Percentage(55),
+    ];
+    let row_style = |i: usize| {
+        let color = match i % 2 {
+            0 => tailwind::SLATE.c950,
+            _ => tailwind::SLATE.c900,
+        };
+        Style::new().fg(tailwind::SLATE.c200).bg(color)
+    };
+    let firing_badge = |status: AlertStatus| match status {
+        AlertStatus::Firing => Span::styled(" FIRING ", Style::default().fg(Color::Black).bg(Color::Red).bold()),
+        AlertStatus::Resolved => Span::styled(" RESOLVED ", Style::default().fg(Color::Black).bg(Color::Green).bold()),
+    };
+
+    let header = ["SINCE", "", "RULE", "BOT", "MESSAGE"]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .style(header_style)
+        .height(1);
+    let rows = state.firing.iter()
+        .enumerate()
+        .map(|(i, (alert, since))| Row::new([
+            Cell::from(format_timestamp(*since)),
+            Cell::from(Line::from(firing_badge(AlertStatus::Firing))),
+            Cell::from(alert.rule.clone()),
+            Cell::from(alert.bot.clone()),
+            Cell::from(alert.message.clone()),
+        ]).style(row_style(i)));
+    let table = Table::new(rows, widths)
+        .header(header)
+        .column_spacing(2)
+        .block(Block::default().borders(Borders::ALL).title(format!("{} firing", state.firing.len())))
+        .bg(tailwind::SLATE.c950);
+    frame.render_widget(table, chunks[1]);
+
+    let header = ["TIME", "", "RULE", "BOT", "MESSAGE"]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .style(header_style)
+        .height(1);
+    state.vertical_scroll = state.vertical_scroll.min(state.history.len().saturating_sub(1));
+    let rows = state.history.iter()
+        .skip(state.vertical_scroll)
+        .enumerate()
+        .map(|(i, event)| Row::new([
+            Cell::from(format_timestamp(event.time)),
+            Cell::from(Line::from(firing_badge(event.status))),
+            Cell::from(event.alert.rule.clone()),
+            Cell::from(event.alert.bot.clone()),
+            Cell::from(event.alert.message.clone()),
+        ]).style(row_style(i)));
+    let table = Table::new(rows, widths)
+        .header(header)
+        .column_spacing(2)
+        .block(Block::default().borders(Borders::ALL).title("History"))
+        .bg(tailwind::SLATE.c950);
+    frame.render_widget(table, chunks[2]);
+}
@@ -1,11 +1,13 @@
+use std::collections::BTreeSet;
 
+use chrono::Utc;
 use color_eyre::eyre::Context;
-use ratatui::{layout::{Constraint, Direction, Layout, Rect}, style::{self, Color, Modifier, Style, Stylize}, text::Text, widgets::{canvas, Block, Borders, Cell, List, ListItem, ListState, Paragraph, Row, Scrollbar, ScrollbarOrientation, StatefulWidget, Table}, Frame};
+use ratatui::{layout::{Constraint, Direction, Layout, Rect}, style::{self, Color, Modifier, Style, Stylize}, text::{Line, Span, Text}, widgets::{Block, Borders, Cell, List, ListItem, ListState, Paragraph, Row, Scrollbar, ScrollbarOrientation, ScrollbarState, Table}, Frame};
 
-use crate::{app::AppState, bot_stats::QueueStats, pages::bot::{BotPageState, BotViewState}};
+use crate::{bot_stats::QueueStats, pages::{bot::{BotPageState, BotSettings, BotViewState}, logs::LogPaneState, queue::queue_name}};
 
 use style::palette::tailwind;
-use super::center_rect;
+use super::{center_rect, chart::render_execution_charts, format_duration, inspector::inspector_lines, format_lag, format_timestamp, health_badge, render_input};
 
 pub fn bot_search_and_select_ui(page_state: &mut BotPageState, area: Rect, frame: &mut Frame) {
     let area = center_rect(area, 80, 80);
@@ -13,7 +15,7 @@ pub fn bot_search_and_select_ui(page_state: &mut BotPageState, area: Rect, frame
         .direction(Direction::Vertical)
         .margin(2)
         .constraints(
-            &[
+            [
                 Constraint::Length(4),
                 Constraint::Length(3),
                 Constraint::Min(4)
@@ -39,7 +41,12 @@ pub fn bot_search_and_select_ui(page_state: &mut BotPageState, area: Rect, frame
         chunks[1].y + 1,
     );
 
-    let items: Vec<ListItem> = page_state.search_results.iter().map(|a| ListItem::new(a.clone())).collect();
+    let items: Vec<ListItem> = page_state.search_results.iter()
+        .map(|a| match page_state.health.get(a) {
+            Some(health) => ListItem::new(Line::from(vec![health_badge(health.status), Span::raw(format!(" {a}"))])),
+            None => ListItem::new(a.clone()),
+        })
+        .collect();
     
     let mut state = ListState::default()
         .with_selected(Some(page_state.current_select_index));
@@ -70,38 +77,188 @@ pub fn bot_view_ui(state: &mut BotViewState, area: Rect, frame: &mut Frame) {
         .direction(Direction::Horizontal)
         .margin(2)
         .constraints(
-            &[
+            [
                 Constraint::Percentage(50),
                 Constraint::Percentage(50),
             ]
         )
         .split(area);
     
-    let settings_json_pretty = serde_json::to_string_pretty(&state.setting).wrap_err("failed to make setting pretty json").unwrap();
-    let num_lines = settings_json_pretty.lines().count();
+    let mut title = vec![Span::raw(state.setting.id.clone())];
+    if let Some(health) = state.health.as_ref() {
+        title.extend([Span::raw(" "), health_badge(health.status), Span::raw(format!(" {} ", health.reason))]);
+    }
+    let title = Line::from(title);
     
-    state.vertical_scroll_state = state.vertical_scroll_state.content_length(num_lines);
+    match state.logs.as_ref() {
+        Some(logs) => log_pane_ui(logs, title, chunks[0], frame),
+        None => {
+            let settings = if state.inspector.raw {
+                Text::from(settings_json(&state.setting))
+            } else {
+                let (lines, headers) = inspector_lines(&state.setting, &state.inspector, Utc::now().timestamp_millis());
+                if state.inspector.follow {
+                    state.inspector.follow = false;
+                    state.vertical_scroll = headers[state.inspector.selected];
+                    state.vertical_scroll_state = state.vertical_scroll_state.position(state.vertical_scroll);
+                }
+                Text::from(lines)
+            };
+            let num_lines = settings.lines.len();
+            
+            state.vertical_scroll_state = state.vertical_scroll_state.content_length(num_lines);
+            
+            let paragraph = Paragraph::new(settings)
+                .block(Block::default().borders(Borders::ALL).title(title))
+                .scroll((state.vertical_scroll as u16, 0));
+            
+            frame.render_widget(paragraph, chunks[0]);
+            frame.render_stateful_widget(Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(Some("^"))
+                .end_symbol(Some("v")),
+            chunks[0],
+            &mut state.vertical_scroll_state);
+        },
+    }
     
-    let paragraph = Paragraph::new(settings_json_pretty)
-        .block(Block::default().borders(Borders::ALL).title(state.setting.id.clone()))
-        .scroll((state.vertical_scroll as u16, 0));
+    let right = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage(50),
+                Constraint::Percentage(50),
+            ]
+        )
+        .split(chunks[1]);
     
-    frame.render_widget(paragraph, chunks[0]);
+    let range = match (state.history.as_ref(), state.history_error.as_ref()) {
+        (Some(_), _) => state.range.to_string(),
+        (None, Some(_)) => format!("{}, fetch failed", state.range),
+        (None, None) => format!("loading {}...", state.range),
+    };
+    render_execution_charts(frame, right[0], &state.series, &range);
+    read_write_tables(state, right[1], frame)
+}
+
+/// The settings as pretty json, with each instance log swapped for a note since they're read in the log pane
+fn settings_json(setting: &BotSettings) -> String {
+    let mut json = serde_json::to_value(setting).wrap_err("failed to make setting json").unwrap();
+    if let Some(instances) = json.get_mut("instances").and_then(|a| a.as_object_mut()) {
+        for (id, instance) in setting.instances.iter().flatten() {
+            if let (Some(log), Some(value)) = (instance.log.as_ref(), instances.get_mut(id)) {
+                value["log"] = format!("{} bytes gzipped, press l to read", log.0.len()).into();
+            }
+        }
+    }
+    serde_json::to_string_pretty(&json).wrap_err("failed to make setting pretty json").unwrap()
+}
+
+fn status_span(status: Option<&str>) -> Span<'static> {
+    let color = match status {
+        Some("complete") => Color::LightGreen,
+        Some("error") => Color::LightRed,
+        _ => Color::Yellow,
+    };
+    Span::styled(status.unwrap_or("-").to_owned(), Style::default().fg(color))
+}
+
+/// A log line with every match of `needle`, already ascii lowercase, highlighted
+fn highlight_line(number: usize, line: &str, needle: &str, current: bool) -> Line<'static> {
+    let line = line.replace('\t', "  ");
+    let mut spans = vec![Span::raw(format!("{:>4} ", number + 1)).dark_gray()];
+    let mut start = 0;
+    if !needle.is_empty() {
+        for (index, _) in line.to_ascii_lowercase().match_indices(needle) {
+            spans.push(Span::raw(line[start..index].to_owned()));
+            spans.push(Span::styled(line[index..index + needle.len()].to_owned(), Style::default().fg(Color::Black).bg(Color::Yellow)));
+            start = index + needle.len();
+        }
+    }
+    spans.push(Span::raw(line[start..].to_owned()));
+    
+    let line = Line::from(spans);
+    if current {
+        line.bg(tailwind::SLATE.c800)
+    } else {
+        line
+    }
+}
+
+/// The selected instance's last run and its log, searchable, in place of the settings
+fn log_pane_ui(logs: &LogPaneState, title: Line, area: Rect, frame: &mut Frame) {
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    
+    let Some((id, instance)) = logs.instance() else {
+        frame.render_widget(Paragraph::new(" no instances have been recorded for this bot").dark_gray(), inner);
+        return;
+    };
+    
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(2),
+                Constraint::Length(3),
+                Constraint::Min(0),
+            ]
+        )
+        .split(inner);
+    
+    let started = instance.start_time.map(format_timestamp).unwrap_or_else(|| "-".to_string());
+    let completed = instance.completed_time.map(format_timestamp).unwrap_or_else(|| "-".to_string());
+    let duration = instance.duration(Utc::now().timestamp_millis()).map(format_duration).unwrap_or_else(|| "-".to_string());
+    let header = vec![
+        Line::from(vec![
+            Span::raw(format!(" instance {id} ({}/{})  status ", logs.selected + 1, logs.instances.len())).bold(),
+            status_span(instance.status.as_deref()),
+            Span::raw("  result ").bold(),
+            status_span(instance.result.as_deref()),
+        ]),
+        Line::from(format!(" started {started}  completed {completed}  took {duration}")),
+    ];
+    frame.render_widget(Paragraph::new(header), chunks[0]);
+    
+    let found = match (logs.search.value().is_empty(), logs.current_match) {
+        (true, _) => String::new(),
+        (false, Some(current)) => format!(" ({}/{})", current + 1, logs.matches.len()),
+        (false, None) => " (no matches)".to_string(),
+    };
+    let search_block = Block::default().borders(Borders::ALL).title(format!("Search{found}"));
+    render_input(&logs.search, search_block, true, chunks[1], frame);
+    
+    let log_block = Block::default().borders(Borders::ALL).title("Log");
+    let lines = match &logs.lines {
+        Err(e) => vec![Line::from(Span::styled(format!(" the log couldn't be read: {e}"), Style::default().fg(Color::LightRed)))],
+        Ok(lines) if lines.is_empty() => vec![Line::from(" no log was kept for this instance").dark_gray()],
+        Ok(lines) => {
+            let needle = logs.search.value().to_ascii_lowercase();
+            let current = logs.current_match.map(|a| logs.matches[a]);
+            lines.iter()
+                .enumerate()
+                .map(|(number, line)| highlight_line(number, line, &needle, current == Some(number)))
+                .collect()
+        },
+    };
+    let mut scroll_state = ScrollbarState::new(lines.len()).position(logs.scroll);
+    let paragraph = Paragraph::new(lines)
+        .block(log_block)
+        .scroll((logs.scroll as u16, 0));
+    frame.render_widget(paragraph, chunks[2]);
     frame.render_stateful_widget(Scrollbar::new(ScrollbarOrientation::VerticalRight)
         .begin_symbol(Some("^"))
         .end_symbol(Some("v")),
-    chunks[0],
-    &mut state.vertical_scroll_state);
-    read_write_tables(state, chunks[1], frame)
+    chunks[2],
+    &mut scroll_state);
 }
 
-
 pub struct TableData {
     queue: String,
     
     // Possibly a little chart here maybe?
     
-    events_written: u32,
+    events_written: u64,
 }
 
 
@@ -129,7 +286,7 @@ pub fn read_write_tables(state: &BotViewState, area: Rect, frame: &mut Frame) {
         .direction(Direction::Vertical)
         .margin(2)
         .constraints(
-            &[
+            [
                 Constraint::Percentage(50),
                 Constraint::Percentage(50),
             ]
@@ -155,7 +312,7 @@ pub fn read_write_tables(state: &BotViewState, area: Rect, frame: &mut Frame) {
                             0 => tailwind::SLATE.c950,
                             _ => tailwind::SLATE.c900,
                         };
-                let t_data = TableData::new(queue, &stats);
+                let t_data = TableData::new(queue, stats);
                 let item = t_data.ref_array();
                 item.into_iter()
                     .map(|content| Cell::from(Text::from(format!("\n{content}\n"))))
@@ -174,22 +331,32 @@ pub fn read_write_tables(state: &BotViewState, area: Rect, frame: &mut Frame) {
         
         frame.render_widget(write_table, chunks[0])
     }
-    if !state.read_stats.is_empty() {
-        let header  = ["QUEUE", "EVENTS WRITTEN (45 min)"]
+    // A stalled reader may have no read stats in the window but still has a checkpoint to lag behind
+    let read_queues: BTreeSet<String> = state.read_stats.keys()
+        .map(|a| queue_name(a))
+        .chain(state.read_lags.keys().cloned())
+        .collect();
+    
+    if !read_queues.is_empty() {
+        let header  = ["QUEUE", "EVENTS READ (45 min)", "TIME BEHIND", "EVENT LAG"]
             .into_iter()
             .map(Cell::from)
             .collect::<Row>()
             .style(header_style)
             .height(1);
         
-        let read_rows = state.read_stats.iter().enumerate()
-            .map(|(i, (queue, stats))| {
+        let read_rows = read_queues.iter().enumerate()
+            .map(|(i, queue)| {
                 let color = match i % 2 {
                             0 => tailwind::SLATE.c950,
                             _ => tailwind::SLATE.c900,
                         };
-                let t_data = TableData::new(queue, &stats);
-                let item = t_data.ref_array();
+                let stats = state.read_stats.iter()
+                    .find(|(id, _)| queue_name(id) == *queue)
+                    .map(|(_, stats)| stats.as_slice())
+                    .unwrap_or_default();
+                let t_data = TableData::new(queue, stats);
+                let item = [t_data.ref_array(), format_lag(state.read_lags.get(queue))].concat();
                 item.into_iter()
                     .map(|content| Cell::from(Text::from(format!("\n{content}\n"))))
                     .collect::<Row>()
@@ -200,7 +367,9 @@ pub fn read_write_tables(state: &BotViewState, area: Rect, frame: &mut Frame) {
         
         let read_table = Table::new(read_rows, [
             Constraint::Length(31),
-            Constraint::Length(35)
+            Constraint::Length(22),
+            Constraint::Length(12),
+            Constraint::Length(12),
         ])
         .header(header.clone())
         .column_spacing(2)
@@ -211,4 +380,3 @@ pub fn read_write_tables(state: &BotViewState, area: Rect, frame: &mut Frame) {
     
     
 }
-
@@ -1,29 +1,21 @@
-use chrono::{DateTime, Duration, Utc};
-use color_eyre::{eyre::bail, owo_colors::OwoColorize};
-use ratatui::{layout::Rect, prelude::*, symbols, widgets::{block::Title, Axis, Block, Chart, Dataset, GraphType, LegendPosition}};
-use style::Styled;
+use chrono::DateTime;
+use ratatui::{layout::Rect, prelude::*, symbols, widgets::{block::Title, Axis, Block, Chart, Dataset, GraphType, Paragraph}};
 
-use crate::app::AppState;
+use crate::bot_stats::ExecutionSeries;
 
 
 
 
 fn calculate_even_distance(min: f64, max: f64, num_divisions: f64) -> f64 {
-    (min + max) / num_divisions
+    (max - min) / num_divisions
 }
 
 fn get_even_distance_points(min: f64, max: f64, num_divisions: u32) -> Vec<f64> {
     let distance = calculate_even_distance(min, max, num_divisions as f64);
-    let mut points = vec![];
-    points.push(min);
     
-    let mut prev_point = min;
-    while prev_point < max {
-        prev_point += distance;
-        points.push(prev_point)
-    }
-    
-    points
+    (0..=num_divisions)
+        .map(|division| min + distance * division as f64)
+        .collect()
 }
 
 fn convert_points_to_labels(points: &[f64]) -> Vec<Span<'_>> {
@@ -32,9 +24,9 @@ fn convert_points_to_labels(points: &[f64]) -> Vec<Span<'_>> {
         .enumerate()
         .map(|(index, point)| {
             if index == 0 ||index == point_len - 1{
-                point.to_string().bold()
+                format!("{point:.0}").bold()
             } else {
-                point.to_string().into()
+                format!("{point:.0}").into()
             }
         }).collect()
 }
@@ -58,109 +50,75 @@ fn convert_timestamps_to_human_labels (points: &[f64]) -> Vec<Span<'_>> {
         }).collect()
 }
 
-pub fn render_executions (frame: &mut Frame, area: Rect, app: &AppState) {
-    let data = vec![
-        (1., 4.), 
-        (2., 3.), 
-        (3., 1.), 
-        (4., 6.)];
-    
-    let dataset = vec![
-        Dataset::default()
-            .name("test".italic())
-            .marker(symbols::Marker::Dot)
-            .style(Style::default().fg(Color::Yellow))
-            .graph_type(GraphType::Line)
-            .data(&data)
-    ];
-    
-    let x_labels = vec![0., 2., 4.];
-    let y_labels = vec![0., 4., 8.];
-    
-    let chart = Chart::new(dataset)
-        .block(Block::bordered().title(
-            Title::default()
-                .content("Executions".cyan().bold())
-                .alignment(Alignment::Center)
-            )
-        )
-        .x_axis(Axis::default()
-            .title("Time")
-            .style(Style::default().gray())
-            .bounds([1., 4.])
-            .labels(convert_points_to_labels(&x_labels))
-        )
-        .y_axis(Axis::default()
-            .title("Executions")
-            .style(Style::default().gray())
-            .bounds([0., 7.])
-            .labels(convert_points_to_labels(&y_labels))
-        )
-        .legend_position(Some(LegendPosition::TopLeft))
-        .hidden_legend_constraints((Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)));
-    
-    frame.render_widget(chart, area)
+/// Renders the four execution charts for a bot in a 2x2 grid
+pub fn render_execution_charts(frame: &mut Frame, area: Rect, series: &ExecutionSeries, range: &str) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+    let top = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(rows[0]);
+    let bottom = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(rows[1]);
+    
+    render_series(frame, top[0], &format!("Completions ({range})"), &series.completions, Color::Yellow);
+    render_series(frame, top[1], &format!("Errors ({range})"), &series.errors, Color::LightRed);
+    render_series(frame, bottom[0], &format!("Units ({range})"), &series.units, Color::Cyan);
+    render_series(frame, bottom[1], &format!("Avg Duration ms ({range})"), &series.avg_duration, Color::Magenta);
 }
 
-pub fn render_executions_full (frame: &mut Frame, area: Rect, app: &AppState) {
-    let data = app.get_chart_data();
+/// Renders a single `(time in seconds, value)` series as a line chart
+pub fn render_series (frame: &mut Frame, area: Rect, title: &str, data: &[(f64, f64)], color: Color) {
+    let block = Block::bordered().title(
+        Title::default()
+            .content(title.to_string().cyan().bold())
+            .alignment(Alignment::Center)
+        );
+    
+    if data.is_empty() {
+        let empty = Paragraph::new("no stats in range")
+            .alignment(Alignment::Center)
+            .block(block);
+        frame.render_widget(empty, area);
+        return;
+    }
     
-    let min_x = data.iter().min_by(|(time_a, _), (time_b, _)| time_a.total_cmp(time_b)).unwrap().0;
-    let max_x = data.iter().max_by(|(time_a, _), (time_b, _)| time_a.total_cmp(time_b)).unwrap().0;
+    let min_x = data.iter().map(|(time, _)| *time).fold(f64::INFINITY, f64::min);
+    let max_x = data.iter().map(|(time, _)| *time).fold(f64::NEG_INFINITY, f64::max);
+    let max_y = data.iter().map(|(_, value)| *value).fold(0.0, f64::max);
     
-    let min_y = data.iter().min_by(|(_, execution_a), (_, execution_b)| execution_a.total_cmp(execution_b)).unwrap().0;
-    let max_y = data.iter().max_by(|(_, execution_a), (_, execution_b)| execution_a.total_cmp(execution_b)).unwrap().0;
+    // A single bucket or an all zero series still needs a range to draw in
+    let max_x = if max_x > min_x { max_x } else { min_x + 1.0 };
+    let max_y = if max_y > 0.0 { max_y } else { 1.0 };
     
-    let x_label_points = get_even_distance_points(min_x, max_x, 3);
-    let y_label_points = get_even_distance_points(min_y, max_y, 3);
-    
-    let x_labels = convert_timestamps_to_human_labels(&x_label_points);
-    if x_labels.is_empty() {
-        panic!("x_labels is empty");
-    }
-    let y_labels = convert_points_to_labels(&y_label_points);
-    if y_labels.is_empty() || y_labels.len() == 1 {
-        println!("{y_labels:?}");
-        panic!("y_labels is empty");
-    }
+    let x_label_points = get_even_distance_points(min_x, max_x, 2);
+    let y_label_points = get_even_distance_points(0.0, max_y, 2);
     
     let dataset = vec![Dataset::default()
-        .name("executions".italic())
         .marker(symbols::Marker::Braille)
-        .style(Style::default().fg(Color::Yellow))
+        .style(Style::default().fg(color))
         .graph_type(GraphType::Line)
-        .data(&data)
+        .data(data)
     ];
     
-    if dataset.is_empty() {
-        panic!("dataset is empty")
-    }
-    
     let chart = Chart::new(dataset)
-        .block(Block::bordered().title(
-            Title::default()
-                .content("Executions".cyan().bold())
-                .alignment(Alignment::Center)
-            )
-        )
+        .block(block)
         .x_axis(Axis::default()
-            .title("Time")
             .style(Style::default().gray())
             .bounds([min_x, max_x])
             .labels(convert_timestamps_to_human_labels(&x_label_points))
         )
         .y_axis(Axis::default()
-            .title("Executions")
             .style(Style::default().gray())
-            .bounds([min_y, max_y])
+            .bounds([0.0, max_y])
             .labels(convert_points_to_labels(&y_label_points))
-        )
-        .legend_position(Some(LegendPosition::TopLeft))
-        .hidden_legend_constraints((Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)));
+        );
     
     frame.render_widget(chart, area)
-    
-    
 }
 
 #[cfg(test)]
@@ -181,4 +139,10 @@ mod chart_tests {
         println!("points = {points:?}");
         assert_eq!(points, vec![0.0, 2.0, 4.0, 6.0, 8.0, 10.0])
     }
+    
+    #[test]
+    fn get_even_distance_points_offset_range() {
+        let points = get_even_distance_points(100.0, 110.0, 2);
+        assert_eq!(points, vec![100.0, 105.0, 110.0])
+    }
 }
\ No newline at end of file
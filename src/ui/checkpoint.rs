@@ -0,0 +1,102 @@
+use ratatui::{layout::{Constraint, Direction, Layout, Rect}, style::{self, Color, Modifier, Style, Stylize}, text::{Line, Span}, widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState}, Frame};
+
+use crate::{event_id::EventId, pages::{checkpoint::{describe_move, CheckpointEditor, CheckpointStep}, queue::queue_name}};
+
+use style::palette::tailwind;
+use super::{format_duration, format_timestamp, render_input};
+
+fn step_block(title: String, active: bool) -> Block<'static> {
+    Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(if active { Style::default().fg(Color::Yellow) } else { Style::default() })
+}
+
+/// An event id with the time it was written, or `-`
+fn checkpoint_label(checkpoint: Option<&str>) -> String {
+    match checkpoint {
+        Some(eid) => match EventId::parse(eid) {
+            Some(parsed) => format!("{eid} ({})", format_timestamp(parsed.timestamp)),
+            None => eid.to_owned(),
+        },
+        None => "-".to_string(),
+    }
+}
+
+pub fn checkpoint_ui(editor: &CheckpointEditor, area: Rect, frame: &mut Frame) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Move a read checkpoint of {} on {}", editor.bot.id, editor.bus));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints(
+            [
+                Constraint::Length(editor.queues.len() as u16 + 3),
+                Constraint::Length(3),
+                Constraint::Length(1),
+                Constraint::Length(6),
+                Constraint::Length(3),
+                Constraint::Length(2),
+                Constraint::Min(0),
+            ]
+        )
+        .split(inner);
+
+    let header = ["QUEUE", "CHECKPOINT", "HEAD", "BEHIND"]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .style(Style::default().fg(tailwind::SLATE.c200).bg(tailwind::BLUE.c900));
+    let rows = editor.queues.iter().map(|(queue, checkpoint)| {
+        let head = editor.heads.get(&queue_name(queue));
+        let behind = checkpoint.as_deref().and_then(EventId::parse)
+            .zip(head.and_then(|a| EventId::parse(a)))
+            .map(|(read, written)| format_duration(written.timestamp - read.timestamp))
+            .unwrap_or_else(|| "-".to_string());
+        Row::new([
+            queue_name(queue),
+            checkpoint_label(checkpoint.as_deref()),
+            head.cloned().unwrap_or_else(|| "-".to_string()),
+            behind,
+        ])
+    });
+    let table = Table::new(rows, [Constraint::Min(20), Constraint::Length(64), Constraint::Length(42), Constraint::Length(10)])
+        .header(header)
+        .column_spacing(2)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol(">>")
+        .block(step_block("1. Queue".to_string(), editor.step == CheckpointStep::Queue));
+    let mut table_state = TableState::default().with_selected(Some(editor.selected));
+    frame.render_stateful_widget(table, chunks[0], &mut table_state);
+
+    let target_active = editor.step == CheckpointStep::Target;
+    render_input(&editor.target, step_block("2. New checkpoint".to_string(), target_active), target_active, chunks[1], frame);
+    let help = Paragraph::new(" an event id (z/...), a UTC time like 2024-07-15 12:30, epoch millis, head or undo").dark_gray();
+    frame.render_widget(help, chunks[2]);
+
+    let diff = match editor.change.as_ref() {
+        Some(change) => vec![
+            Line::from(format!("  {}", change.queue)),
+            Line::from(Span::styled(format!("- {}", checkpoint_label(change.before.as_deref())), Style::default().fg(Color::LightRed))),
+            Line::from(Span::styled(format!("+ {}", checkpoint_label(Some(&change.after))), Style::default().fg(Color::LightGreen))),
+            Line::from(format!("  {}", describe_move(change))).bold(),
+        ],
+        None => vec![Line::from("  enter a new checkpoint to see the change").dark_gray()],
+    };
+    let diff = Paragraph::new(diff).block(Block::default().borders(Borders::ALL).title("Before / After"));
+    frame.render_widget(diff, chunks[3]);
+
+    let confirm_active = editor.step == CheckpointStep::Confirm;
+    let title = format!("3. Type {} to confirm", editor.confirm_name());
+    render_input(&editor.confirm, step_block(title, confirm_active), confirm_active, chunks[4], frame);
+
+    let note = match editor.error.as_ref() {
+        Some(e) => Line::from(Span::styled(format!(" {e}"), Style::default().fg(Color::LightRed))),
+        None => Line::from(" the current checkpoint is saved as an undo record before anything is written").dark_gray(),
+    };
+    frame.render_widget(Paragraph::new(note), chunks[5]);
+}
@@ -0,0 +1,43 @@
+use ratatui::{layout::{Alignment, Rect}, style::{Color, Style, Stylize}, text::{Line, Span}, widgets::{Block, Borders, Clear, Paragraph, Wrap}, Frame};
+
+use crate::actions::{BotAction, PendingAction};
+
+use super::center_rect;
+
+/// Asks before pausing, resuming or triggering a bot, showing what will be written and where
+pub fn action_dialog(pending: &PendingAction, bus: &str, table: &str, area: Rect, frame: &mut Frame) {
+    let area = center_rect(area, 50, 30);
+    let change = match pending.action {
+        BotAction::Pause => "paused will be set to true",
+        BotAction::Resume => "paused will be set to false",
+        BotAction::Trigger => "trigger will be set to now so it runs on the next cron tick",
+        BotAction::Checkpoint => "a read checkpoint will be moved",
+    };
+    let text = vec![
+        Line::from(vec![
+            Span::raw(format!("{} ", pending.action)).bold(),
+            Span::styled(pending.bot.id.clone(), Style::default().fg(Color::Cyan)),
+            Span::raw(format!(" on {bus}?")).bold(),
+        ]),
+        Line::default(),
+        Line::from(format!("{change} on its entry in {table}.")),
+        Line::from("The write is skipped if the bot changed since it was loaded.").dark_gray(),
+        Line::default(),
+        Line::from(vec![
+            Span::styled(" y ", Style::default().fg(Color::Black).bg(Color::Yellow)),
+            Span::raw(" confirm   "),
+            Span::styled(" any other key ", Style::default().fg(Color::Black).bg(Color::Gray)),
+            Span::raw(" cancel"),
+        ]),
+    ];
+    let paragraph = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title("Confirm Write"));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(paragraph, area);
+}
@@ -0,0 +1,76 @@
+use ratatui::{layout::{Constraint, Direction, Layout, Rect}, style::{Color, Modifier, Style, Stylize}, text::{Line, Span}, widgets::{Block, Borders, List, ListItem, ListState, Paragraph}, Frame};
+
+use crate::pages::entries::{EntryBrowserState, EntryTable};
+
+use super::render_input;
+
+/// The bots using the selected system with the one Enter opens highlighted
+fn link_lines(browser: &EntryBrowserState) -> Vec<Line<'static>> {
+    let bots = browser.linked_bots();
+    if bots.is_empty() {
+        return vec![Line::from("no bots reference this system").dark_gray(), Line::default()];
+    }
+
+    let mut spans = vec![Span::raw("Bots  ").dark_gray()];
+    for (index, bot) in bots.iter().enumerate() {
+        let span = Span::raw(bot.clone());
+        spans.push(match index == browser.selected_link {
+            true => span.add_modifier(Modifier::REVERSED),
+            false => span.cyan(),
+        });
+        spans.push(Span::raw("  "));
+    }
+    vec![Line::from(spans), Line::default()]
+}
+
+pub fn entries_ui(browser: &EntryBrowserState, area: Rect, frame: &mut Frame) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .split(area);
+
+    let status = match (&browser.error, browser.loading) {
+        (_, true) => Line::from(" loading... ").fg(Color::Yellow),
+        (Some(error), false) => Line::from(format!(" {error} ")).fg(Color::LightRed),
+        (None, false) => Line::from(format!(" {} of {} ", browser.results.len(), browser.entries.len())).dark_gray(),
+    };
+    let search = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Search {}", browser.table))
+        .title_bottom(status);
+    render_input(&browser.search, search, true, chunks[0], frame);
+
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .split(chunks[1]);
+
+    let items = browser.results.iter().map(|a| {
+        let entry = &browser.entries[*a];
+        let mut spans = vec![Span::raw(entry.id.clone())];
+        if let Some(label) = entry.label() {
+            spans.push(Span::raw(format!("  {label}")).dark_gray());
+        }
+        ListItem::new(Line::from(spans))
+    });
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut list_state = ListState::default().with_selected(browser.selected_entry().map(|_| browser.selected));
+    frame.render_stateful_widget(list, panes[0], &mut list_state);
+
+    let lines = match browser.selected_entry() {
+        Some(entry) => {
+            let mut lines = match browser.table {
+                EntryTable::System => link_lines(browser),
+                EntryTable::Settings => vec![],
+            };
+            let json = serde_json::to_string_pretty(&entry.to_json()).unwrap_or_default();
+            lines.extend(json.lines().skip(browser.detail_scroll).map(|a| Line::from(a.to_owned())));
+            lines
+        },
+        None => vec![Line::from("no entry selected").dark_gray()],
+    };
+    let title = browser.selected_entry().map(|a| a.id.clone()).unwrap_or_default();
+    frame.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)), panes[1]);
+}
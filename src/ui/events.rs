@@ -0,0 +1,117 @@
+use chrono::Utc;
+use ratatui::{layout::{Constraint, Direction, Layout, Rect}, style::{self, Color, Modifier, Style, Stylize}, text::{Line, Span}, widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Wrap}, Frame};
+use serde_json::Value;
+
+use crate::{pages::events::EventBrowserState, stream::StreamEvent};
+
+use style::palette::tailwind;
+use super::{format_age, format_timestamp, render_input};
+
+fn field(label: &str, value: impl Into<Span<'static>>) -> Line<'static> {
+    Line::from(vec![Span::raw(format!("{label:<16}")).dark_gray(), value.into()])
+}
+
+fn time_value(millis: Option<i64>, now: i64) -> String {
+    match millis {
+        Some(millis) if millis > 0 => format!("{} ({})", format_timestamp(millis), format_age(millis, now)),
+        _ => "-".to_string(),
+    }
+}
+
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+}
+
+/// The selected event's ids and times, then its payload scrolled by `scroll`
+fn event_lines(event: &StreamEvent, scroll: usize, now: i64) -> Vec<Line<'static>> {
+    let correlation = event.correlation_id.as_ref().map(pretty).unwrap_or_else(|| "-".to_string());
+    let mut lines = vec![
+        field("eid", event.eid.clone()),
+        field("bot", event.id.clone().unwrap_or_else(|| "-".to_string())),
+        field("written", time_value(event.timestamp, now)),
+        field("source time", time_value(event.event_source_timestamp, now)),
+        Line::default(),
+        Line::from("Correlation").cyan().bold(),
+    ];
+    lines.extend(correlation.lines().map(|a| Line::from(a.to_owned())));
+    lines.push(Line::default());
+    lines.push(Line::from("Payload").cyan().bold());
+
+    let payload = match (&event.payload, &event.s3) {
+        (Some(payload), _) => pretty(payload),
+        (None, Some(s3)) => format!("too big for the table, it's in s3 at {}", pretty(s3)),
+        (None, None) => "-".to_string(),
+    };
+    lines.extend(payload.lines().skip(scroll).map(|a| Line::from(a.to_owned())));
+    lines
+}
+
+pub fn events_ui(browser: &EventBrowserState, area: Rect, frame: &mut Frame) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .split(area);
+
+    match &browser.jump {
+        Some(jump) => {
+            let mut block = Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow))
+                .title("Go to an event id, a UTC time, epoch millis, start, end or bot:<id> (Enter to go, Esc to cancel)");
+            if let Some(note) = &browser.note {
+                block = block.title_bottom(Line::from(format!(" {note} ")).fg(Color::LightRed));
+            }
+            render_input(jump, block, true, chunks[0], frame);
+        },
+        None => {
+            let status = match (&browser.note, browser.loading) {
+                (_, true) => Span::styled("loading...", Style::default().fg(Color::Yellow)),
+                (Some(note), false) => Span::styled(note.clone(), Style::default().fg(Color::LightRed)),
+                (None, false) => Span::raw(format!("{} events", browser.events.len())).dark_gray(),
+            };
+            let checkpoint = match &browser.checkpoint {
+                Some((bot, eid)) => Span::raw(format!("{bot} is at {eid}  ")).yellow(),
+                None => Span::default(),
+            };
+            let header = Paragraph::new(Line::from(vec![Span::raw(format!("{}  ", browser.position)), checkpoint, status]))
+                .block(Block::default().borders(Borders::ALL).title(format!("Events on {}", browser.queue)));
+            frame.render_widget(header, chunks[0]);
+        },
+    }
+
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(chunks[1]);
+
+    let header = ["EID", "WRITTEN", "BOT", ""]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .style(Style::default().fg(tailwind::SLATE.c200).bg(tailwind::BLUE.c900));
+    let rows = browser.events.iter().map(|event| {
+        let marker = match &browser.checkpoint {
+            Some((_, eid)) if *eid == event.eid => Cell::from("◀").style(Style::default().fg(Color::Yellow)),
+            _ => Cell::from(""),
+        };
+        Row::new([
+            Cell::from(event.eid.clone()),
+            Cell::from(event.timestamp.map(format_timestamp).unwrap_or_else(|| "-".to_string())),
+            Cell::from(event.id.clone().unwrap_or_else(|| "-".to_string())),
+            marker,
+        ])
+    });
+    let table = Table::new(rows, [Constraint::Length(42), Constraint::Length(19), Constraint::Length(22), Constraint::Min(10)])
+        .header(header)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL));
+    let mut table_state = TableState::default().with_selected(browser.selected_event().map(|_| browser.selected));
+    frame.render_stateful_widget(table, panes[0], &mut table_state);
+
+    let details = Block::default().borders(Borders::ALL).title("Event");
+    let lines = match browser.selected_event() {
+        Some(event) => event_lines(event, browser.payload_scroll, Utc::now().timestamp_millis()),
+        None => vec![Line::from("no event selected").dark_gray()],
+    };
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }).block(details), panes[1]);
+}
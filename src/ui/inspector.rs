@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use ratatui::{style::{Color, Modifier, Style, Stylize}, text::{Line, Span}};
+
+use crate::pages::{bot::{BotSettings, CheckpointDetail}, inspector::{SettingsInspector, SettingsSection}, queue::queue_name};
+
+use super::{format_age, format_duration, format_timestamp};
+
+fn field(label: &str, value: impl Into<Span<'static>>) -> Line<'static> {
+    Line::from(vec![Span::raw(format!("  {label:<20}")).dark_gray(), value.into()])
+}
+
+/// A time as a date and how long ago it was, or how long until it comes around
+fn time_value(millis: Option<i64>, now: i64) -> String {
+    match millis {
+        Some(millis) if millis > 0 => format!("{} ({})", format_timestamp(millis), format_age(millis, now)),
+        _ => "-".to_string(),
+    }
+}
+
+fn text_value(value: Option<&str>) -> String {
+    value.filter(|a| !a.is_empty()).unwrap_or("-").to_owned()
+}
+
+fn checkpoint_lines(checkpoints: Option<&HashMap<String, CheckpointDetail>>, now: i64) -> Vec<Line<'static>> {
+    let mut queues: Vec<_> = checkpoints.into_iter().flatten().collect();
+    if queues.is_empty() {
+        return vec![Line::from("  none").dark_gray()];
+    }
+    queues.sort_by(|a, b| a.0.cmp(b.0));
+
+    queues.into_iter()
+        .flat_map(|(queue, detail)| [
+            Line::from(format!("  {}", queue_name(queue))).bold(),
+            field("  checkpoint", detail.checkpoint.as_ref().map(|a| a.to_string()).unwrap_or_else(|| "-".to_string())),
+            field("  records", detail.records.map(|a| a.to_string()).unwrap_or_else(|| "-".to_string())),
+            field("  ended", time_value(detail.ended_timestamp.as_ref().and_then(|a| a.as_i64()), now)),
+            field("  source", time_value(detail.source_timestamp.as_ref().and_then(|a| a.as_i64()), now)),
+        ])
+        .collect()
+}
+
+fn instance_lines(setting: &BotSettings, now: i64) -> Vec<Line<'static>> {
+    let mut instances: Vec<_> = setting.instances.iter().flatten().collect();
+    if instances.is_empty() {
+        return vec![Line::from("  none").dark_gray()];
+    }
+    instances.sort_by(|a, b| a.0.cmp(b.0));
+
+    let header = Line::from(format!("  {:<4}{:<10}{:<10}{:<34}{:<10}{}", "ID", "STATUS", "RESULT", "STARTED", "TOOK", "LOG"))
+        .style(Style::default().fg(Color::Gray).add_modifier(Modifier::BOLD));
+    let rows = instances.into_iter().map(|(id, instance)| {
+        let color = match instance.status.as_deref() {
+            Some("complete") => Color::LightGreen,
+            Some("error") => Color::LightRed,
+            _ => Color::Yellow,
+        };
+        let log = match instance.log.as_ref() {
+            Some(_) => "press l",
+            None => "-",
+        };
+        Line::from(vec![
+            Span::raw(format!("  {id:<4}")),
+            Span::styled(format!("{:<10}", text_value(instance.status.as_deref())), Style::default().fg(color)),
+            Span::raw(format!("{:<10}", text_value(instance.result.as_deref()))),
+            Span::raw(format!("{:<34}", time_value(instance.start_time, now))),
+            Span::raw(format!("{:<10}", instance.duration(now).map(format_duration).unwrap_or_else(|| "-".to_string()))),
+            Span::raw(log).dark_gray(),
+        ])
+    });
+
+    [header].into_iter().chain(rows).collect()
+}
+
+fn section_lines(setting: &BotSettings, section: SettingsSection, now: i64) -> Vec<Line<'static>> {
+    match section {
+        SettingsSection::Details => {
+            let paused = match setting.paused.unwrap_or_default() {
+                true => Span::styled("yes", Style::default().fg(Color::LightBlue)),
+                false => Span::raw("no"),
+            };
+            let errors = setting.error_count.unwrap_or_default();
+            let errors = match errors {
+                0 => Span::raw("0"),
+                a => Span::styled(a.to_string(), Style::default().fg(Color::LightRed)),
+            };
+            let tags = setting.tags.as_deref()
+                .map(|a| a.split(',').map(str::trim).filter(|a| !a.is_empty()).collect::<Vec<_>>().join(", "));
+            vec![
+                field("name", text_value(setting.name.as_deref())),
+                field("description", text_value(setting.description.as_deref())),
+                field("lambda", text_value(setting.lambda_name.as_deref())),
+                field("type", text_value(setting.r_type.as_deref())),
+                field("execution type", text_value(setting.execution_type.as_deref())),
+                field("tags", text_value(tags.as_deref())),
+                field("paused", paused),
+                field("errors in a row", errors),
+                field("message", text_value(setting.message.as_deref())),
+            ]
+        },
+        SettingsSection::Schedule => vec![
+            field("invoked", time_value(setting.invoke_time, now)),
+            field("trigger", time_value(setting.trigger, now)),
+            field("scheduled trigger", time_value(setting.scheduled_trigger, now)),
+            field("token", time_value(setting.token, now)),
+        ],
+        SettingsSection::Triggers => match setting.triggers.as_deref() {
+            Some(triggers) if !triggers.is_empty() => triggers.iter()
+                .map(|a| Line::from(format!("  {}", queue_name(a))))
+                .collect(),
+            _ => vec![Line::from("  none, it only runs on its schedule").dark_gray()],
+        },
+        SettingsSection::ReadCheckpoints => checkpoint_lines(setting.checkpoints.as_ref().and_then(|a| a.read.as_ref()), now),
+        SettingsSection::WriteCheckpoints => checkpoint_lines(setting.checkpoints.as_ref().and_then(|a| a.write.as_ref()), now),
+        SettingsSection::Instances => instance_lines(setting, now),
+    }
+}
+
+/// The settings as sections, returning the lines along with where each section's header is
+pub fn inspector_lines(setting: &BotSettings, inspector: &SettingsInspector, now: i64) -> (Vec<Line<'static>>, Vec<usize>) {
+    let mut lines = vec![];
+    let mut headers = vec![];
+
+    for (index, section) in SettingsSection::ALL.into_iter().enumerate() {
+        if index > 0 {
+            lines.push(Line::default());
+        }
+        headers.push(lines.len());
+        let collapsed = inspector.collapsed.contains(&section);
+        let header = Line::from(format!("{} {}", if collapsed { "▸" } else { "▾" }, section.title()))
+            .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD));
+        lines.push(if index == inspector.selected { header.reversed() } else { header });
+        if !collapsed {
+            lines.extend(section_lines(setting, section, now));
+        }
+    }
+
+    (lines, headers)
+}
+
+#[cfg(test)]
+mod inspector_tests {
+    use crate::{pages::inspector::{SettingsInspector, SettingsSection}, source::fixtures};
+
+    use super::inspector_lines;
+
+    fn text(lines: &[ratatui::text::Line]) -> Vec<String> {
+        lines.iter().map(|a| a.spans.iter().map(|a| a.content.as_ref()).collect::<String>().trim_end().to_owned()).collect()
+    }
+
+    #[test]
+    fn sections_show_dates_and_collapse() {
+        let bots = fixtures::bots();
+        let enricher = bots.iter().find(|a| a.id == "bot:order_enricher").unwrap();
+        let mut inspector = SettingsInspector::default();
+
+        let (lines, headers) = inspector_lines(enricher, &inspector, 1721044800000);
+        let lines = text(&lines);
+        assert_eq!(headers.len(), SettingsSection::ALL.len());
+        assert_eq!(lines[headers[3]], "▾ Read checkpoints");
+        assert!(lines.contains(&"    ended             2024-07-15 11:59:00 (1m 0s ago)".to_string()), "{lines:#?}");
+
+        inspector.selected = 3;
+        inspector.toggle_section();
+        let (collapsed, _) = inspector_lines(enricher, &inspector, 1721044800000);
+        let collapsed = text(&collapsed);
+        assert!(collapsed.contains(&"▸ Read checkpoints".to_string()));
+        assert!(collapsed.len() < lines.len());
+        assert!(!collapsed.contains(&"    ended             2024-07-15 11:59:00 (1m 0s ago)".to_string()));
+    }
+}
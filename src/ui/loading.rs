@@ -1,4 +1,4 @@
-use ratatui::{layout::{Constraint, Direction, Layout, Rect}, Frame};
+use ratatui::{layout::{Constraint, Direction, Layout, Rect}, style::{Color, Style}, text::Line, widgets::{Block, Borders, Paragraph, Wrap}, Frame};
 
 use crate::app::AppState;
 
@@ -6,28 +6,48 @@ use super::center_rect;
 
 pub fn loading(app: &mut AppState, area: Rect, frame: &mut Frame) {
     let area = center_rect(area, 45, 45);
-    // let chunks = Layout::default()
-    //     .direction(Direction::Horizontal)
-    //     .margin(1)
-    //     .constraints(
-    //         [
-    //             Constraint::Percentage(50),
-    //             Constraint::Percentage(50),
-    //         ]
-    //         .as_ref(),
-    //     )
-    //     .split(area);
-
-    // Simple random step
-    // let simple = throbber_widgets_tui::Throbber::default();
-    // frame.render_widget(simple, chunks[0]);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints(
+            [
+                Constraint::Length(1),
+                Constraint::Min(0),
+            ]
+        )
+        .split(area);
+
+    let bus = app.selected_bus.clone().unwrap_or_default();
 
     // Set full with state
     let full = throbber_widgets_tui::Throbber::default()
-        .label("Running...")
+        .label(format!("Loading {bus}..."))
         .style(ratatui::style::Style::default().fg(ratatui::style::Color::Cyan))
         .throbber_style(ratatui::style::Style::default().fg(ratatui::style::Color::Red).add_modifier(ratatui::style::Modifier::BOLD))
         .throbber_set(throbber_widgets_tui::CLOCK)
         .use_type(throbber_widgets_tui::WhichUse::Spin);
-    frame.render_stateful_widget(full, area, &mut app.throbber_state);
-}
\ No newline at end of file
+    frame.render_stateful_widget(full, chunks[0], &mut app.throbber_state);
+
+    let progress = &app.load_progress;
+    let (cron_table, stats_table, event_table) = match app.source.as_ref() {
+        Some(source) => (source.bots_location(), source.stats_location(), source.queues_location()),
+        None => (String::from("leo_cron"), String::from("leo_stats"), String::from("leo_event")),
+    };
+
+    let mut lines = vec![
+        Line::from(format!("{cron_table}: {} bots scanned ({} pages, {:.1} RCU)", progress.cron.items, progress.cron.pages, progress.cron.consumed_capacity)),
+        Line::from(format!("{stats_table}: {} stats queried ({} pages, {:.1} RCU)", progress.stats.items, progress.stats.pages, progress.stats.consumed_capacity)),
+        Line::from(format!("{event_table}: {} queues scanned ({} pages, {:.1} RCU)", progress.event.items, progress.event.pages, progress.event.consumed_capacity)),
+    ];
+
+    if let Some(e) = progress.error.as_ref() {
+        lines.push(Line::default());
+        lines.push(Line::styled(format!("load failed: {e}"), Style::default().fg(Color::LightRed)));
+        lines.push(Line::from("press Esc to choose another bus"));
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title("Progress"))
+        .wrap(Wrap { trim: true });
+    frame.render_widget(paragraph, chunks[1]);
+}
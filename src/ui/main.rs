@@ -8,7 +8,14 @@ pub fn main_ui(app: &mut AppState, area: Rect, frame: &mut Frame) {
     let area = center_rect(area, 50, 50);
     let items = [
         ListItem::new("Bot Details"),
-        ListItem::new("Queue Details")
+        ListItem::new("Queue Details"),
+        ListItem::new("Bot Overview"),
+        ListItem::new(match app.alerts.firing.len() {
+            0 => "Alerts".to_string(),
+            firing => format!("Alerts ({firing} firing)"),
+        }),
+        ListItem::new("Systems"),
+        ListItem::new("Settings"),
     ];
     
     let mut state = ListState::default()
@@ -1,30 +1,77 @@
+use std::time::Instant;
+
+use alerts::alerts_ui;
 use bot::{bot_search_and_select_ui, bot_view_ui};
-use chart::render_executions;
-use color_eyre::eyre::bail;
+use checkpoint::checkpoint_ui;
+use chrono::DateTime;
+use dialog::action_dialog;
+use entries::entries_ui;
+use events::events_ui;
 use itertools::Itertools;
 use loading::loading;
 use main::main_ui;
-use ratatui::{layout::{self, Constraint, Direction, Layout, Rect}, style::{Color, Stylize}, text::{Line, Span}, widgets::Paragraph, Frame};
+use overview::overview_ui;
+use queue::{queue_search_and_select_ui, queue_view_ui};
+use topology::topology_ui;
+use ratatui::{layout::{self, Constraint, Direction, Layout, Rect}, style::{Color, Style, Stylize}, text::{Line, Span}, widgets::{Block, Paragraph}, Frame};
+use tui_input::Input;
 
-use crate::{app::{AppState, AppTab}, THEME};
+use crate::{app::{AppState, AppTab}, health::HealthStatus, lag::ReadLag, THEME};
 mod chart;
 mod main;
 mod bot;
 mod bus_select;
 mod loading;
+mod queue;
+mod overview;
+mod alerts;
+mod topology;
+mod dialog;
+mod checkpoint;
+mod inspector;
+mod events;
+mod entries;
 
 pub fn render_ui(frame: &mut Frame, app: &mut AppState) {
     let area = center_rect(frame.size(), 95, 95);
+    let (keys, status) = (key_bindings(app), refresh_status(app));
+    // The status moves to its own line when the keys would run into it
+    let bar_height = if keys.width() + status.width() > area.width as usize { 2 } else { 1 };
     let layout = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .constraints([Constraint::Min(0), Constraint::Length(bar_height)])
         .split(area);
     
     match app.mode {
         crate::app::AppTab::Main => main_ui(app, layout[0], frame),
         crate::app::AppTab::Bot => bot_search_and_select_ui(&mut app.bot_page, layout[0], frame),
-        crate::app::AppTab::Queue => todo!(),
-        crate::app::AppTab::BotView => match &mut app.bot_page.selected_bot {
+        crate::app::AppTab::Queue => queue_search_and_select_ui(&mut app.queue_page, layout[0], frame),
+        AppTab::Overview => overview_ui(&mut app.overview_page, layout[0], frame),
+        AppTab::Alerts => alerts_ui(&mut app.alerts, layout[0], frame),
+        AppTab::Topology => match &mut app.topology {
+            Some(topology) => topology_ui(topology, layout[0], frame),
+            None => panic!("cannot view a topology that wasn't built"),
+        },
+        AppTab::Events => match &app.events {
+            Some(browser) => events_ui(browser, layout[0], frame),
+            None => panic!("cannot browse events without a queue"),
+        },
+        AppTab::Entries => match &app.entries {
+            Some(browser) => entries_ui(browser, layout[0], frame),
+            None => panic!("cannot browse entries without a table"),
+        },
+        AppTab::Checkpoint => match &app.checkpoint {
+            Some(editor) => checkpoint_ui(editor, layout[0], frame),
+            None => panic!("cannot edit a checkpoint without a bot"),
+        },
+        crate::app::AppTab::QueueView => {
+            let range = app.queue_page.range_label();
+            match &mut app.queue_page.selected_queue {
+                Some(queue) => queue_view_ui(queue, &range, layout[0], frame),
+                None => panic!("cannot view non-existant queue"),
+            }
+        }
+        crate::app::AppTab::BotView | AppTab::BotLog => match &mut app.bot_page.selected_bot {
             Some(bot) => bot_view_ui(bot, layout[0], frame),
             None => panic!("cannot view non-existant bot"),
         }
@@ -32,7 +79,12 @@ pub fn render_ui(frame: &mut Frame, app: &mut AppState) {
         AppTab::Loading => loading(app, area, frame),
        }
     
-    render_bottom_bar(&app.mode, layout[1], frame)
+    if let Some(pending) = app.pending_action.as_ref() {
+        let table = app.source.as_ref().map(|a| a.bots_location()).unwrap_or_default();
+        action_dialog(pending, app.selected_bus.as_deref().unwrap_or_default(), &table, layout[0], frame);
+    }
+    
+    render_bottom_bar(keys, status, layout[1], frame)
     // Split the area when we want to show other charts
     // render_executions(frame, area, app)
     
@@ -60,8 +112,24 @@ fn center_rect(r: Rect, percent_x: u16, percent_y: u16) -> Rect {
         .split(popup_layout[1])[1]
 }
 
-pub fn render_bottom_bar(mode: &AppTab, area: Rect, f: &mut Frame) {
-    let keys = mode.get_keys();
+/// A text input in `block`, with the cursor in it when it has focus
+pub fn render_input(input: &Input, block: Block, active: bool, area: Rect, frame: &mut Frame) {
+    let width = area.width.max(3) - 3;
+    let scroll = input.visual_scroll(width as usize);
+    let paragraph = Paragraph::new(input.value())
+        .style(Style::default().fg(Color::Yellow))
+        .scroll((0, scroll as u16))
+        .block(block);
+    frame.render_widget(paragraph, area);
+
+    if active {
+        frame.set_cursor(area.x + (input.visual_cursor().max(scroll) - scroll) as u16 + 1, area.y + 1);
+    }
+}
+
+/// The current mode's key bindings
+fn key_bindings(app: &AppState) -> Line<'static> {
+    let keys = app.mode.get_keys();
     let spans = keys
         .iter()
         .flat_map(|(key, desc)| {
@@ -70,9 +138,155 @@ pub fn render_bottom_bar(mode: &AppTab, area: Rect, f: &mut Frame) {
             [key, desc]
         })
         .collect_vec();
-    let paragraph = Paragraph::new(Line::from(spans))
+    Line::from(spans)
+}
+
+pub fn render_bottom_bar(keys: Line<'static>, status: Line<'static>, area: Rect, f: &mut Frame) {
+    let paragraph = Paragraph::new(keys)
         .alignment(layout::Alignment::Center)
         .fg(Color::Indexed(236));
         // .bg(Color::Indexed(232));
-    f.render_widget(paragraph, area)
+    
+    if area.height > 1 {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Length(1)])
+            .split(area);
+        f.render_widget(paragraph, chunks[0]);
+        f.render_widget(Paragraph::new(status).alignment(layout::Alignment::Right), chunks[1]);
+        return;
+    }
+    
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(status.width() as u16)])
+        .split(area);
+    
+    f.render_widget(paragraph, chunks[0]);
+    f.render_widget(status, chunks[1])
+}
+
+/// How long ago the data was refreshed and how long until the next refresh
+fn refresh_status(app: &AppState) -> Line<'static> {
+    let Some(last_refresh) = app.last_refresh else {
+        return Line::default();
+    };
+    
+    let now = Instant::now();
+    let status = match (&app.refresh_error, &app.refresh_warning) {
+        (Some(e), _) => Span::styled(
+            format!(" refresh failed: {} ", e.lines().next().unwrap_or_default().chars().take(60).collect::<String>()),
+            THEME.key_binding.key.fg(Color::LightRed),
+        ),
+        (None, Some(warning)) => Span::styled(
+            format!(" refreshed {}s ago, {warning} ", now.duration_since(last_refresh).as_secs()),
+            THEME.key_binding.key.fg(Color::Yellow),
+        ),
+        (None, None) => Span::styled(
+            format!(" refreshed {}s ago ", now.duration_since(last_refresh).as_secs()),
+            THEME.key_binding.description,
+        ),
+    };
+    let cost = match app.last_load.as_ref() {
+        Some(load) => Span::styled(
+            format!(" {} items / {} pages / {:.1} RCU ", load.items(), load.pages(), load.consumed_capacity()),
+            THEME.key_binding.description,
+        ),
+        None => Span::default(),
+    };
+    let countdown = if app.is_refreshing() {
+        Span::styled(" refreshing... ", THEME.key_binding.key)
+    } else {
+        Span::styled(
+            format!(" next in {}s ", app.refresh_at.saturating_duration_since(now).as_secs()),
+            THEME.key_binding.key,
+        )
+    };
+    
+    let alerts = match app.alerts.firing.len() {
+        0 => Span::default(),
+        firing => Span::styled(format!(" {firing} alerts firing "), THEME.key_binding.key.fg(Color::LightRed)),
+    };
+    
+    let action = match app.action_status.as_ref() {
+        Some(Ok(done)) => Span::styled(format!(" {done} "), THEME.key_binding.key.fg(Color::LightGreen)),
+        Some(Err(e)) => Span::styled(
+            format!(" {} ", e.lines().next().unwrap_or_default().chars().take(80).collect::<String>()),
+            THEME.key_binding.key.fg(Color::LightRed),
+        ),
+        None => Span::default(),
+    };
+    
+    Line::from(vec![action, alerts, cost, status, countdown])
+}
+
+/// Formats a span of millis with its two largest units, like `2h 5m` or `45s`
+pub fn format_duration(millis: i64) -> String {
+    let seconds = millis.max(0) / 1000;
+    let (days, hours, minutes, seconds) = (seconds / 86400, seconds / 3600 % 24, seconds / 60 % 60, seconds % 60);
+
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{seconds}s"),
+        (0, 0, _) => format!("{minutes}m {seconds}s"),
+        (0, _, _) => format!("{hours}h {minutes}m"),
+        _ => format!("{days}d {hours}h"),
+    }
+}
+
+/// How long before `now` a time was, or how long until it comes around
+pub fn format_age(millis: i64, now: i64) -> String {
+    if millis <= now {
+        format!("{} ago", format_duration(now - millis))
+    } else {
+        format!("in {}", format_duration(millis - now))
+    }
+}
+
+/// The time behind and event lag cells for a read, `-` when there's nothing to measure
+pub fn format_lag(lag: Option<&ReadLag>) -> [String; 2] {
+    let Some(lag) = lag else {
+        return ["-".to_string(), "-".to_string()];
+    };
+    let time_behind = lag.time_behind.map(format_duration).unwrap_or_else(|| "-".to_string());
+    let event_lag = match lag.event_lag {
+        _ if lag.caught_up => "caught up".to_string(),
+        Some(event_lag) => format_duration(event_lag),
+        None => "-".to_string(),
+    };
+
+    [time_behind, event_lag]
+}
+
+/// A colored label for a bot's health
+pub fn health_badge(status: HealthStatus) -> Span<'static> {
+    let color = match status {
+        HealthStatus::Healthy => Color::Green,
+        HealthStatus::Idle => Color::Gray,
+        HealthStatus::Paused => Color::Blue,
+        HealthStatus::Warning => Color::Yellow,
+        HealthStatus::Critical => Color::Red,
+    };
+    Span::styled(format!(" {status:<8} "), Style::default().fg(Color::Black).bg(color).bold())
+}
+
+/// Formats epoch millis as a human readable UTC date
+pub fn format_timestamp(millis: i64) -> String {
+    match DateTime::from_timestamp_millis(millis) {
+        Some(date) if millis > 0 => date.format("%Y-%m-%d %H:%M:%S").to_string(),
+        _ => "-".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod ui_tests {
+    use super::format_duration;
+
+    #[test]
+    fn durations_use_the_two_largest_units() {
+        assert_eq!(format_duration(45_000), "45s");
+        assert_eq!(format_duration(125_000), "2m 5s");
+        assert_eq!(format_duration(7_330_000), "2h 2m");
+        assert_eq!(format_duration(190_800_000), "2d 5h");
+        assert_eq!(format_duration(-5), "0s");
+    }
 }
@@ -0,0 +1,113 @@
+use ratatui::{layout::{Constraint, Direction, Layout, Rect}, style::{self, Color, Modifier, Style, Stylize}, text::Line, widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState}, Frame};
+
+use crate::{dynamo::StatsRange, pages::overview::{OverviewColumn, OverviewPageState}};
+
+use style::palette::tailwind;
+use super::{format_lag, format_timestamp, health_badge};
+
+pub fn overview_ui(page_state: &mut OverviewPageState, area: Rect, frame: &mut Frame) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(2)
+        .constraints(
+            [
+                Constraint::Length(3),
+                Constraint::Min(4)
+            ]
+        )
+        .split(area);
+    let width = chunks[0].width.max(3) - 3;
+    let scroll = page_state.filter.visual_scroll(width as usize);
+
+    let input = Paragraph::new(page_state.filter.value())
+        .style(Style::default().fg(Color::Yellow))
+        .scroll((0, scroll as u16))
+        .block(Block::default().borders(Borders::ALL).title("Filter"));
+
+    frame.render_widget(input, chunks[0]);
+
+    frame.set_cursor(
+        // Put the cursor past the end of the input text
+        chunks[0].x
+        + ((page_state.filter.visual_cursor()).max(scroll) - scroll) as u16
+        + 1,
+        // Move one line down, from the border to the input line
+        chunks[0].y + 1,
+    );
+
+    let header_style = Style::default()
+        .fg(tailwind::SLATE.c200)
+        .bg(tailwind::BLUE.c900);
+
+    let header = OverviewColumn::ALL.iter()
+        .map(|column| match column == &page_state.sort {
+            true => Cell::from(format!("{} {}", column.title(), if page_state.descending { "▼" } else { "▲" }))
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+            false => Cell::from(column.title()),
+        })
+        .collect::<Row>()
+        .style(header_style)
+        .height(1);
+
+    let rows = page_state.visible_rows()
+        .enumerate()
+        .map(|(i, row)| {
+            let color = match i % 2 {
+                0 => tailwind::SLATE.c950,
+                _ => tailwind::SLATE.c900,
+            };
+            let health = match row.health.as_ref() {
+                Some(health) => Cell::from(Line::from(health_badge(health.status))),
+                None => Cell::from("-"),
+            };
+            Row::new([
+                Cell::from(row.name.clone()),
+                health,
+                Cell::from(if row.paused { "yes" } else { "no" }),
+                Cell::from(row.last_invoke.map(format_timestamp).unwrap_or_else(|| "-".to_string())),
+                Cell::from(row.error_count.to_string()),
+                Cell::from(row.executions.to_string()),
+                Cell::from(row.errors.to_string()),
+                Cell::from(row.units.to_string()),
+                Cell::from(format_lag(row.lag.as_ref())[1].clone()),
+            ])
+            .style(Style::new().fg(tailwind::SLATE.c200).bg(color))
+            .height(1)
+        })
+        .collect::<Vec<Row>>();
+
+    let reason = page_state.selected()
+        .and_then(|a| Some(format!(" {}: {} ", a.name, a.health.as_ref()?.reason)))
+        .unwrap_or_default();
+
+    let table = Table::new(rows, [
+        Constraint::Min(24),
+        Constraint::Length(12),
+        Constraint::Length(8),
+        Constraint::Length(21),
+        Constraint::Length(13),
+        Constraint::Length(12),
+        Constraint::Length(8),
+        Constraint::Length(8),
+        Constraint::Length(11),
+    ])
+    .header(header)
+    .column_spacing(2)
+    .highlight_style(
+        Style::default()
+            .add_modifier(Modifier::BOLD)
+            .add_modifier(Modifier::REVERSED)
+            .fg(Color::LightRed)
+    )
+    .block(Block::default().borders(Borders::ALL).title(format!(
+        "{} of {} bots, stats over {}",
+        page_state.visible.len(),
+        page_state.rows.len(),
+        StatsRange::LOAD,
+    )).title_bottom(reason))
+    .bg(tailwind::SLATE.c950);
+
+    let mut state = TableState::default()
+        .with_selected(Some(page_state.current_select_index));
+    frame.render_stateful_widget(table, chunks[1], &mut state);
+}
@@ -0,0 +1,169 @@
+use ratatui::{layout::{Constraint, Direction, Layout, Rect}, style::{self, Color, Modifier, Style, Stylize}, text::{Line, Text}, widgets::{Block, Borders, Cell, List, ListItem, ListState, Paragraph, Row, Table}, Frame};
+
+use crate::pages::queue::{QueueConnection, QueuePageState, QueueViewState};
+
+use style::palette::tailwind;
+use super::{center_rect, format_lag, format_timestamp};
+
+pub fn queue_search_and_select_ui(page_state: &mut QueuePageState, area: Rect, frame: &mut Frame) {
+    let area = center_rect(area, 80, 80);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(2)
+        .constraints(
+            [
+                Constraint::Length(4),
+                Constraint::Length(3),
+                Constraint::Min(4)
+            ]
+        )
+        .split(area);
+    let width = chunks[0].width.max(3) - 3;
+    let scroll = page_state.search.visual_scroll(width as usize);
+
+    let input = Paragraph::new(page_state.search.value())
+        .style(Style::default().fg(Color::Yellow))
+        .scroll((0, scroll as u16))
+        .block(Block::default().borders(Borders::ALL).title("Queue Select"));
+
+    frame.render_widget(input, chunks[1]);
+
+    frame.set_cursor(
+        // Put the cursor past the end of the input text
+        chunks[1].x
+        + ((page_state.search.visual_cursor()).max(scroll) - scroll) as u16
+        + 1,
+        // Move one line down, from the border to the input line
+        chunks[1].y + 1,
+    );
+
+    let items: Vec<ListItem> = page_state.search_results.iter().map(|a| ListItem::new(a.clone())).collect();
+
+    let mut state = ListState::default()
+        .with_selected(Some(page_state.current_select_index));
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(format!("{} queues", page_state.search_results.len())))
+        .style(Style::new().white().on_black())
+        .highlight_style(
+            Style::default()
+                .add_modifier(Modifier::BOLD)
+                .add_modifier(Modifier::REVERSED)
+                .fg(Color::LightRed)
+        )
+        .highlight_symbol(">>");
+    frame.render_stateful_widget(list, chunks[2], &mut state);
+}
+
+pub fn queue_view_ui(state: &mut QueueViewState, range: &str, area: Rect, frame: &mut Frame) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(2)
+        .constraints(
+            [
+                Constraint::Length(10),
+                Constraint::Percentage(50),
+                Constraint::Percentage(50),
+            ]
+        )
+        .split(area);
+
+    let latest = state.latest_write();
+    let record = state.record.as_ref();
+    let archive = record.and_then(|a| a.archive.as_ref());
+    let idle = match (state.writers.is_empty(), record) {
+        (true, Some(_)) => " (idle, nothing written in this range)",
+        _ => "",
+    };
+    let summary = vec![
+        Line::from(format!("Events Written: {}{idle}", state.events_written())),
+        Line::from(format!("Head: {}", state.head().unwrap_or_else(|| "-".to_string()))),
+        Line::from(format!("Latest Source Timestamp: {}", latest.map(|a| format_timestamp(a.source_timestamp)).unwrap_or_else(|| "-".to_string()))),
+        Line::from(format!("Latest Write: {}", state.last_written().map(format_timestamp).unwrap_or_else(|| "-".to_string()))),
+        Line::from(format!("Archived To: {}", match archive {
+            Some(archive) => format!(
+                "{} (ran {})",
+                archive.end.as_deref().unwrap_or("-"),
+                archive.timestamp.map(format_timestamp).unwrap_or_else(|| "-".to_string()),
+            ),
+            None => "-".to_string(),
+        })),
+        Line::from(format!("Owner: {}", record.and_then(|a| a.owner.clone()).unwrap_or_else(|| "-".to_string()))),
+        Line::from(format!("Settings: {}", record.and_then(|a| a.settings.as_ref()).map(|a| a.to_string()).unwrap_or_else(|| "-".to_string()))),
+        Line::from(format!("Slowest Reader: {}", match state.slowest_reader() {
+            Some(reader) => format!("{} ({} behind)", reader.bot, format_lag(reader.lag.as_ref())[1]),
+            None => "-".to_string(),
+        })),
+    ];
+
+    let paragraph = Paragraph::new(summary)
+        .block(Block::default().borders(Borders::ALL).title(format!("{} ({range})", state.name)));
+    frame.render_widget(paragraph, chunks[0]);
+
+    connection_table("WRITTEN BY", "EVENTS WRITTEN", &state.writers, false, state.vertical_scroll, chunks[1], frame);
+    connection_table("READ BY", "EVENTS READ", &state.readers, true, state.vertical_scroll, chunks[2], frame);
+}
+
+fn connection_table(bot_title: &str, units_title: &str, connections: &[QueueConnection], show_lag: bool, skip: usize, area: Rect, frame: &mut Frame) {
+    let header_style = Style::default()
+        .fg(tailwind::SLATE.c200)
+        .bg(tailwind::BLUE.c900);
+
+    let mut titles = vec![bot_title, units_title, "CHECKPOINT", "SOURCE TIMESTAMP"];
+    let mut widths = vec![
+        Constraint::Percentage(35),
+        Constraint::Percentage(15),
+        Constraint::Percentage(30),
+        Constraint::Percentage(20),
+    ];
+    if show_lag {
+        titles.extend(["TIME BEHIND", "EVENT LAG"]);
+        widths = vec![
+            Constraint::Percentage(25),
+            Constraint::Percentage(10),
+            Constraint::Percentage(27),
+            Constraint::Percentage(16),
+            Constraint::Percentage(11),
+            Constraint::Percentage(11),
+        ];
+    }
+
+    let header = titles
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .style(header_style)
+        .height(1);
+
+    let rows = connections.iter()
+        .skip(skip.min(connections.len().saturating_sub(1)))
+        .enumerate()
+        .map(|(i, connection)| {
+            let color = match i % 2 {
+                0 => tailwind::SLATE.c950,
+                _ => tailwind::SLATE.c900,
+            };
+            let mut cells = vec![
+                connection.bot.clone(),
+                connection.stats.units.to_string(),
+                connection.stats.checkpoint.clone().unwrap_or_default(),
+                format_timestamp(connection.stats.source_timestamp),
+            ];
+            if show_lag {
+                cells.extend(format_lag(connection.lag.as_ref()));
+            }
+            cells.into_iter()
+                .map(|content| Cell::from(Text::from(content)))
+                .collect::<Row>()
+                .style(Style::new().fg(tailwind::SLATE.c200).bg(color))
+                .height(1)
+        });
+
+    let table = Table::new(rows, widths)
+    .header(header)
+    .column_spacing(2)
+    .block(Block::default().borders(Borders::ALL).title(format!("{} bots", connections.len())))
+    .bg(tailwind::SLATE.c950);
+
+    frame.render_widget(table, area)
+}
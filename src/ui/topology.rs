@@ -0,0 +1,78 @@
+use ratatui::{layout::{Alignment, Constraint, Direction, Layout, Rect}, style::{Color, Modifier, Style, Stylize}, text::{Line, Span}, widgets::{Block, Borders, HighlightSpacing, List, ListItem, ListState, Paragraph}, Frame};
+
+use crate::{dynamo::StatsRange, pages::topology::{Side, TopologyState, TreeItem}, topology::Node};
+
+fn node_span(node: &Node) -> Span<'static> {
+    match node {
+        Node::Bot(name) => Span::styled(format!("bot {name}"), Style::default().fg(Color::Cyan)),
+        Node::Queue(name) => Span::styled(format!("queue {name}"), Style::default().fg(Color::Magenta)),
+    }
+}
+
+fn tree_line(item: &TreeItem, side: Side) -> Line<'static> {
+    let indent = match item.depth {
+        1 => String::new(),
+        depth => format!("{}└ ", "  ".repeat(depth - 2)),
+    };
+    let edge = Style::default().fg(Color::DarkGray);
+    match side {
+        Side::Upstream => Line::from(vec![Span::raw(indent), node_span(&item.node), Span::styled(format!(" ──{}──▶", item.units), edge)]),
+        Side::Downstream => Line::from(vec![Span::raw(indent), Span::styled(format!("──{}──▶ ", item.units), edge), node_span(&item.node)]),
+    }
+}
+
+pub fn topology_ui(state: &mut TopologyState, area: Rect, frame: &mut Frame) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Topology (events over {})", StatsRange::LOAD));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .margin(1)
+        .constraints(
+            [
+                Constraint::Percentage(40),
+                Constraint::Percentage(20),
+                Constraint::Percentage(40),
+            ]
+        )
+        .split(inner);
+
+    for (side, items, title, chunk) in [
+        (Side::Upstream, &state.upstream, "Upstream", chunks[0]),
+        (Side::Downstream, &state.downstream, "Downstream", chunks[2]),
+    ] {
+        let active = state.side == side;
+        let list = List::new(items.iter().map(|a| ListItem::new(tree_line(a, side))).collect::<Vec<_>>())
+            .block(Block::default()
+                .borders(Borders::ALL)
+                .title(format!("{title} ({})", items.len()))
+                .border_style(if active { Style::default().fg(Color::Yellow) } else { Style::default() }))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(">>")
+            .highlight_spacing(HighlightSpacing::Always);
+        let mut list_state = ListState::default()
+            .with_selected(active.then_some(state.selected));
+        frame.render_stateful_widget(list, chunk, &mut list_state);
+    }
+
+    let events_in: u64 = state.upstream.iter().filter(|a| a.depth == 1).map(|a| a.units).sum();
+    let events_out: u64 = state.downstream.iter().filter(|a| a.depth == 1).map(|a| a.units).sum();
+    let focus = vec![
+        Line::from(node_span(&state.focus).add_modifier(Modifier::BOLD)),
+        Line::default(),
+        Line::from(format!("{events_in} events in")),
+        Line::from(format!("{events_out} events out")),
+        Line::default(),
+        Line::from(match state.trail.last() {
+            Some(previous) => format!("back to {}", previous.name()),
+            None => String::new(),
+        }).dark_gray(),
+    ];
+    let paragraph = Paragraph::new(focus)
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).title("Focus"));
+    frame.render_widget(paragraph, chunks[1]);
+}